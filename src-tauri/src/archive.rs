@@ -0,0 +1,72 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    cache, ensure_notes_dir, frontmatter, library_notes, links, move_to_folder, read_note_content,
+    system_time_ms, ARCHIVE_DIR,
+};
+
+const ARCHIVE_LOG_FILE: &str = "archive.log";
+const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Moves a note into the archive folder.
+pub fn archive_note(dir: &Path, id: &str) -> Result<PathBuf, String> {
+    move_to_folder(dir, ARCHIVE_DIR, id)
+}
+
+fn log_archived(dir: &Path, line: &str) {
+    let path = dir.join(ARCHIVE_DIR).join(ARCHIVE_LOG_FILE);
+    let written = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{line}"));
+    if let Err(e) = written {
+        eprintln!("Failed to write archive log: {e}");
+    }
+}
+
+/// Archives every note whose `expires_ms` lies in the past. Returns the ids moved.
+pub fn archive_expired(app: &tauri::AppHandle) -> Result<Vec<String>, String> {
+    let dir = ensure_notes_dir(app)?;
+    let now = system_time_ms(SystemTime::now());
+
+    let mut archived = Vec::new();
+    for (id, path) in library_notes(app, &dir)? {
+        let content = read_note_content(&path)?;
+        let Some(expires_ms) =
+            frontmatter::field(&content, "expires_ms").and_then(|v| v.parse::<u64>().ok())
+        else {
+            continue;
+        };
+        if expires_ms > now {
+            continue;
+        }
+
+        archive_note(&dir, &id)?;
+        log_archived(&dir, &format!("{now}\t{id}\texpired at {expires_ms}"));
+        archived.push(id);
+    }
+
+    if !archived.is_empty() {
+        cache::clear(app);
+        links::invalidate(app);
+    }
+    Ok(archived)
+}
+
+/// Checks for expired notes now and then once a minute for the app's lifetime.
+pub fn start_expiry_task(app: &tauri::AppHandle) {
+    let app = app.clone();
+    thread::spawn(move || loop {
+        if let Err(e) = archive_expired(&app) {
+            eprintln!("Failed to archive expired notes: {e}");
+        }
+        thread::sleep(EXPIRY_CHECK_INTERVAL);
+    });
+}
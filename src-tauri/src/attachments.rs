@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+use tauri::Manager;
+
+use crate::{
+    cache, ensure_notes_dir, existing_note_path, library_notes, links, note_files,
+    read_note_content, transaction::Transaction, watcher, ARCHIVE_DIR, ATTACHMENTS_DIR, TRASH_DIR,
+};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DedupReport {
+    /// Each removed duplicate mapped to the copy kept in its place.
+    replaced: BTreeMap<String, String>,
+    /// Notes whose embeds were pointed at a kept copy.
+    notes_updated: usize,
+    bytes_saved: u64,
+}
+
+/// Every file under the attachments folder as `(.attachments/..., path)`.
+fn attachment_files(dir: &Path) -> Result<Vec<(String, PathBuf)>, String> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.join(ATTACHMENTS_DIR)];
+    while let Some(folder) = pending.pop() {
+        if !folder.is_dir() {
+            continue;
+        }
+        let entries =
+            fs::read_dir(&folder).map_err(|e| format!("Failed to read attachments: {e}"))?;
+        for entry in entries {
+            let path = entry
+                .map_err(|e| format!("Failed to read attachments: {e}"))?
+                .path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if let Ok(rel) = path.strip_prefix(dir) {
+                let rel = rel.to_string_lossy().replace('\\', "/");
+                files.push((rel, path));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Every note that can embed attachments: the library's, plus those in the
+/// trash and archive, which can come back.
+pub fn every_note(app: &tauri::AppHandle, dir: &Path) -> Result<Vec<(String, PathBuf)>, String> {
+    let mut notes = library_notes(app, dir)?;
+    for folder in [dir.join(TRASH_DIR), dir.join(ARCHIVE_DIR)] {
+        if folder.is_dir() {
+            notes.extend(note_files(&folder)?);
+        }
+    }
+    Ok(notes)
+}
+
+/// Replaces attachments with identical content by a single copy: the one
+/// with the shortest path (then the first alphabetically) is kept, embeds of
+/// the others are pointed at it, then the others are deleted. Notes in the
+/// trash and archive are updated too, so restoring them doesn't break.
+#[tauri::command]
+pub fn dedup_attachments(app: tauri::AppHandle) -> Result<DedupReport, String> {
+    let dir = ensure_notes_dir(&app)?;
+
+    let mut by_hash: HashMap<Vec<u8>, Vec<(String, PathBuf)>> = HashMap::new();
+    for (rel, path) in attachment_files(&dir)? {
+        let bytes = fs::read(&path).map_err(|e| format!("Failed to read attachment: {e}"))?;
+        by_hash
+            .entry(Sha256::digest(&bytes).to_vec())
+            .or_default()
+            .push((rel, path));
+    }
+
+    let mut report = DedupReport::default();
+    let mut duplicates = Vec::new();
+    for mut group in by_hash.into_values().filter(|group| group.len() > 1) {
+        group.sort_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+        let kept = group[0].0.clone();
+        for (rel, path) in group.into_iter().skip(1) {
+            report.replaced.insert(rel, kept.clone());
+            duplicates.push(path);
+        }
+    }
+    if duplicates.is_empty() {
+        return Ok(report);
+    }
+
+    let moves: HashMap<String, String> = report
+        .replaced
+        .iter()
+        .map(|(from, to)| (from.clone(), to.clone()))
+        .collect();
+    let _paused = watcher::pause(&app);
+    let mut txn = Transaction::new();
+    for (_, path) in every_note(&app, &dir)? {
+        let content = read_note_content(&path)?;
+        let updated = links::rewrite_attachment_refs(&dir, &content, &moves);
+        if updated != content {
+            txn.write(&path, updated);
+            report.notes_updated += 1;
+        }
+    }
+    // Notes first: if that fails nothing has been deleted yet.
+    txn.commit()?;
+    cache::clear(&app);
+    links::invalidate(&app);
+
+    for path in duplicates {
+        let len = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        fs::remove_file(&path).map_err(|e| format!("Failed to remove duplicate: {e}"))?;
+        report.bytes_saved += len;
+    }
+    Ok(report)
+}
+
+/// Absolute path of an attachment embedded by note `id` as `rel_path`
+/// (e.g. `./.attachments/x.png`), for the webview to load through the asset
+/// protocol. The file must exist inside the attachments folder; `..` and
+/// symlinks leading out of it are rejected.
+#[tauri::command]
+pub fn resolve_attachment(
+    app: tauri::AppHandle,
+    id: String,
+    rel_path: String,
+) -> Result<String, String> {
+    let dir = ensure_notes_dir(&app)?;
+    // Notes sit directly in the notes dir, so note-relative is dir-relative.
+    existing_note_path(&dir, &id)?;
+    let rel = links::attachment_key(&dir, &rel_path)
+        .ok_or_else(|| format!("Not an attachment path: {rel_path}"))?;
+
+    let root = dir
+        .join(ATTACHMENTS_DIR)
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve attachments dir: {e}"))?;
+    let path = dir
+        .join(&rel)
+        .canonicalize()
+        .map_err(|_| format!("Attachment not found: {rel}"))?;
+    if !path.starts_with(&root) || !path.is_file() {
+        return Err(format!("Not an attachment path: {rel_path}"));
+    }
+    // The asset protocol serves nothing by default; open it up one resolved
+    // file at a time.
+    app.asset_protocol_scope()
+        .allow_file(&path)
+        .map_err(|e| format!("Failed to allow attachment: {e}"))?;
+    Ok(path.to_string_lossy().to_string())
+}
@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard, TryLockError},
+    thread,
+    time::{Duration, SystemTime},
+};
+use tauri::Manager;
+
+use crate::{
+    checkpoints, confirm, ensure_notes_dir, file_times_ms, fsutil, hash, library_notes, settings,
+    sidecar, system_time_ms, tree, TRASH_DIR,
+};
+
+const AUTO_BACKUP_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const HOUR_MS: u64 = 60 * 60 * 1000;
+
+/// Held while a backup is being written, so a scheduled one never starts
+/// on top of another (or of a manual one).
+#[derive(Default)]
+pub struct BackupLock(Mutex<()>);
+
+/// Note ids that differ between two backups, going from the first to the second.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BackupDiff {
+    added: Vec<String>,
+    removed: Vec<String>,
+    modified: Vec<String>,
+}
+
+/// Backups live next to (not inside) the notes dir, one folder per backup
+/// named after its creation time in milliseconds.
+pub fn backups_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let base = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {e}"))?;
+    Ok(base.join("backups"))
+}
+
+/// Creation times of all backups, oldest first. Folders not named like a
+/// backup are ignored.
+pub fn backup_times(root: &Path) -> Result<Vec<u64>, String> {
+    if !root.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut times = Vec::new();
+    let entries = fs::read_dir(root).map_err(|e| format!("Failed to read backups: {e}"))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read backups: {e}"))?;
+        if !entry.path().is_dir() {
+            continue;
+        }
+        if let Ok(ms) = entry.file_name().to_string_lossy().parse::<u64>() {
+            times.push(ms);
+        }
+    }
+    times.sort_unstable();
+    Ok(times)
+}
+
+fn backup_path(app: &tauri::AppHandle, name: &str) -> Result<PathBuf, String> {
+    let path = backups_dir(app)?.join(name.trim());
+    if name.trim().parse::<u64>().is_err() || !path.is_dir() {
+        return Err(format!("Backup not found: {}", name.trim()));
+    }
+    Ok(path)
+}
+
+/// Content hash of every note in a backup. Backups never change once
+/// written, so the hashes are computed once and kept in a file next to the
+/// backup folder (not inside it, where a restore would copy it back).
+fn backup_hashes(backup: &Path) -> Result<HashMap<String, String>, String> {
+    let cache = backup.with_extension("hashes.json");
+    let cached: HashMap<String, String> = sidecar::load(&cache)?;
+    if !cached.is_empty() {
+        return Ok(cached);
+    }
+
+    let mut hashes = HashMap::new();
+    for (id, rel) in tree::nested_notes(backup)? {
+        let path = backup.join(rel);
+        let bytes = fs::read(&path).map_err(|e| format!("Failed to read backup note: {e}"))?;
+        hashes.insert(id, hash::hex_hash(&bytes));
+    }
+    if !hashes.is_empty() {
+        sidecar::save(&cache, &hashes)?;
+    }
+    Ok(hashes)
+}
+
+/// The backup lock, or `None` while another backup runs.
+fn try_lock_backups(lock: &BackupLock) -> Option<MutexGuard<'_, ()>> {
+    match lock.0.try_lock() {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+/// Copies the library (without the trash) to a new backup. Returns its name.
+#[tauri::command]
+pub fn backup_now(app: tauri::AppHandle) -> Result<String, String> {
+    let lock = app.state::<BackupLock>();
+    let _running =
+        try_lock_backups(&lock).ok_or_else(|| "Backup already in progress".to_string())?;
+    write_backup(&app)
+}
+
+fn write_backup(app: &tauri::AppHandle) -> Result<String, String> {
+    let dir = ensure_notes_dir(app)?;
+    let name = system_time_ms(SystemTime::now()).to_string();
+    let target = backups_dir(app)?.join(&name);
+    if target.exists() {
+        return Err(format!("Backup already exists: {name}"));
+    }
+
+    if let Err(e) = fsutil::copy_dir_excluding(&dir, &target, &[TRASH_DIR]) {
+        // A partial copy must not be mistaken for the latest backup.
+        let _ = fs::remove_dir_all(&target);
+        return Err(e);
+    }
+    Ok(name)
+}
+
+/// Deletes the oldest backups (and their hash caches) beyond the newest
+/// `keep`. Returns the names removed.
+fn prune_backups(app: &tauri::AppHandle, keep: usize) -> Result<Vec<String>, String> {
+    let root = backups_dir(app)?;
+    let times = backup_times(&root)?;
+    let excess = times.len().saturating_sub(keep);
+
+    let mut removed = Vec::new();
+    for ms in &times[..excess] {
+        let path = root.join(ms.to_string());
+        fs::remove_dir_all(&path).map_err(|e| format!("Failed to remove backup {ms}: {e}"))?;
+        let _ = fs::remove_file(path.with_extension("hashes.json"));
+        removed.push(ms.to_string());
+    }
+    Ok(removed)
+}
+
+/// When the next scheduled backup is due: an interval after the latest
+/// backup (manual ones count), or now if there is none. `None` when
+/// `auto_backup_interval_hours` is 0.
+fn next_backup_ms(app: &tauri::AppHandle) -> Result<Option<u64>, String> {
+    let hours = settings::load(app)?.auto_backup_interval_hours;
+    if hours == 0 {
+        return Ok(None);
+    }
+    let now = system_time_ms(SystemTime::now());
+    Ok(Some(match backup_times(&backups_dir(app)?)?.last() {
+        Some(latest) => latest.saturating_add(hours.saturating_mul(HOUR_MS)),
+        None => now,
+    }))
+}
+
+fn run_auto_backup(app: &tauri::AppHandle) -> Result<(), String> {
+    let Some(due) = next_backup_ms(app)? else {
+        return Ok(());
+    };
+    if due > system_time_ms(SystemTime::now()) {
+        return Ok(());
+    }
+
+    // A backup still running (scheduled or manual) covers this one.
+    let lock = app.state::<BackupLock>();
+    let Some(_running) = try_lock_backups(&lock) else {
+        return Ok(());
+    };
+    write_backup(app)?;
+    let keep = settings::load(app)?.backup_retention;
+    if keep > 0 {
+        prune_backups(app, keep)?;
+    }
+    Ok(())
+}
+
+/// Checks once a minute whether a scheduled backup is due, for the app's
+/// lifetime. Interval changes in settings apply from the next check.
+pub fn start_auto_backup_task(app: &tauri::AppHandle) {
+    let app = app.clone();
+    thread::spawn(move || loop {
+        if let Err(e) = run_auto_backup(&app) {
+            eprintln!("Scheduled backup failed: {e}");
+        }
+        thread::sleep(AUTO_BACKUP_CHECK_INTERVAL);
+    });
+}
+
+/// When the next scheduled backup will run (ms since epoch), or `None` when
+/// auto-backup is off.
+#[tauri::command]
+pub fn next_backup_time(app: tauri::AppHandle) -> Result<Option<u64>, String> {
+    next_backup_ms(&app)
+}
+
+/// Ids of notes modified after the most recent backup; every note when there
+/// is no backup yet.
+#[tauri::command]
+pub fn unbacked_changes(app: tauri::AppHandle) -> Result<Vec<String>, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let latest = backup_times(&backups_dir(&app)?)?.last().copied();
+
+    let mut ids = Vec::new();
+    for (id, path) in library_notes(&app, &dir)? {
+        let (_, modified) = file_times_ms(&path)?;
+        if latest.is_none_or(|backup_ms| modified > backup_ms) {
+            ids.push(id);
+        }
+    }
+    ids.sort();
+    Ok(ids)
+}
+
+/// Replaces the library with backup `name`. Current notes go to the trash
+/// first, like restoring a checkpoint.
+#[tauri::command]
+pub fn restore_backup(app: tauri::AppHandle, name: String, confirmed: bool) -> Result<(), String> {
+    confirm::require_confirmation(&app, "restore_backup", confirmed)?;
+    let source = backup_path(&app, &name)?;
+    checkpoints::restore_snapshot(&app, &source)
+}
+
+/// Which notes backup `b` adds, removes and changes compared to backup `a`.
+/// With `a` as the current state, that's what restoring `b` would do.
+#[tauri::command]
+pub fn diff_backups(app: tauri::AppHandle, a: String, b: String) -> Result<BackupDiff, String> {
+    let before = backup_hashes(&backup_path(&app, &a)?)?;
+    let after = backup_hashes(&backup_path(&app, &b)?)?;
+
+    let mut diff = BackupDiff::default();
+    let ids: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    for id in ids {
+        match (before.get(id), after.get(id)) {
+            (None, Some(_)) => diff.added.push(id.clone()),
+            (Some(_), None) => diff.removed.push(id.clone()),
+            (Some(x), Some(y)) if x != y => diff.modified.push(id.clone()),
+            _ => {}
+        }
+    }
+    Ok(diff)
+}
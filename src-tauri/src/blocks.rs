@@ -0,0 +1,224 @@
+// Splits a note into top-level blocks for block-by-block editing. Like the rest
+// of the markdown helpers this is line-based, not a full CommonMark parser.
+
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+use crate::{
+    cache, ensure_notes_dir, existing_note_path, frontmatter, links,
+    markdown::{heading, is_fence},
+    pin, read_note_content, sanitize_id,
+    transaction::Transaction,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockKind {
+    /// The `---` block at the top of the note; always the first block.
+    Frontmatter,
+    Heading,
+    Paragraph,
+    Code,
+    List,
+    Quote,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Block {
+    pub kind: BlockKind,
+    /// Source text of the block, without the final line break.
+    pub text: String,
+    /// First and last line of the block in the file, 1-based and inclusive.
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Byte range of the block's lines in the file, line breaks included.
+    #[serde(skip)]
+    pub range: Range<usize>,
+}
+
+fn is_list_item(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with(['-', '*', '+']) {
+        return trimmed[1..].starts_with([' ', '\t']) || trimmed[1..].trim().is_empty();
+    }
+    let digits = trimmed.bytes().take_while(u8::is_ascii_digit).count();
+    digits > 0 && trimmed[digits..].starts_with(['.', ')']) && {
+        let rest = &trimmed[digits + 1..];
+        rest.is_empty() || rest.starts_with([' ', '\t'])
+    }
+}
+
+fn is_quote(line: &str) -> bool {
+    line.trim_start().starts_with('>')
+}
+
+fn kind_of(line: &str) -> BlockKind {
+    if is_fence(line) {
+        BlockKind::Code
+    } else if heading(line).is_some() {
+        BlockKind::Heading
+    } else if is_list_item(line) {
+        BlockKind::List
+    } else if is_quote(line) {
+        BlockKind::Quote
+    } else {
+        BlockKind::Paragraph
+    }
+}
+
+/// Whether `line` still belongs to an open block of `kind` (blank lines and
+/// closing fences are handled by the caller).
+fn continues(kind: BlockKind, line: &str) -> bool {
+    match kind {
+        BlockKind::Heading | BlockKind::Frontmatter => false,
+        BlockKind::Code => true,
+        // Indented lines are continuations or nested items.
+        BlockKind::List => is_list_item(line) || line.starts_with([' ', '\t']),
+        BlockKind::Quote => is_quote(line),
+        BlockKind::Paragraph => !is_fence(line) && heading(line).is_none(),
+    }
+}
+
+/// The blocks of `content`, in order.
+pub fn parse_blocks(content: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut offset = 0;
+    let mut line_no = 0;
+
+    let (fm, body) = frontmatter::split(content);
+    let body_start = content.len() - body.len();
+    if fm.is_some() {
+        let lines = content[..body_start].matches('\n').count();
+        blocks.push(Block {
+            kind: BlockKind::Frontmatter,
+            text: content[..body_start]
+                .trim_end_matches(['\n', '\r'])
+                .to_string(),
+            start_line: 1,
+            end_line: lines,
+            range: 0..body_start,
+        });
+        offset = body_start;
+        line_no = lines;
+    }
+
+    let mut open: Option<(BlockKind, usize, usize)> = None;
+    let close = |blocks: &mut Vec<Block>,
+                 (kind, start, first): (BlockKind, usize, usize),
+                 end: usize,
+                 last: usize| {
+        blocks.push(Block {
+            kind,
+            text: content[start..end]
+                .trim_end_matches(['\n', '\r'])
+                .to_string(),
+            start_line: first,
+            end_line: last,
+            range: start..end,
+        });
+    };
+
+    for line in content[offset..].split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        line_no += 1;
+        let blank = line.trim().is_empty();
+
+        if let Some(current) = open {
+            let (kind, block_start, _) = current;
+            if kind == BlockKind::Code {
+                // Everything up to and including the closing fence.
+                if is_fence(line) && start != block_start {
+                    close(&mut blocks, current, offset, line_no);
+                    open = None;
+                }
+                continue;
+            }
+            if !blank && continues(kind, line) {
+                continue;
+            }
+            close(&mut blocks, current, start, line_no - 1);
+            open = None;
+        }
+
+        if !blank {
+            let kind = kind_of(line);
+            if kind == BlockKind::Heading {
+                close(&mut blocks, (kind, start, line_no), offset, line_no);
+            } else {
+                open = Some((kind, start, line_no));
+            }
+        }
+    }
+    if let Some(current) = open {
+        close(&mut blocks, current, offset, line_no);
+    }
+    blocks
+}
+
+#[tauri::command]
+pub fn note_blocks(app: tauri::AppHandle, id: String) -> Result<Vec<Block>, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    Ok(parse_blocks(&read_note_content(&path)?))
+}
+
+/// Moves block `block_index` (as numbered by `note_blocks`) of one note to the
+/// end of another. The blank lines that separated it from the next block go
+/// with it, so the source doesn't end up with a double gap.
+#[tauri::command]
+pub fn move_block(
+    app: tauri::AppHandle,
+    from_id: String,
+    block_index: usize,
+    to_id: String,
+) -> Result<(), String> {
+    if sanitize_id(&from_id) == sanitize_id(&to_id) {
+        return Err("Source and target note must differ".to_string());
+    }
+    let dir = ensure_notes_dir(&app)?;
+    let from_path = existing_note_path(&dir, &from_id)?;
+    let to_path = existing_note_path(&dir, &to_id)?;
+
+    let source = read_note_content(&from_path)?;
+    let blocks = parse_blocks(&source);
+    let block = blocks
+        .get(block_index)
+        .ok_or_else(|| format!("Block index out of range: {block_index}"))?;
+    if block.kind == BlockKind::Frontmatter {
+        return Err("The frontmatter block can't be moved".to_string());
+    }
+
+    let cut_end = match blocks.get(block_index + 1) {
+        Some(next) => next.range.start,
+        None => source.len(),
+    };
+    let mut remaining = format!("{}{}", &source[..block.range.start], &source[cut_end..]);
+    if cut_end == source.len() {
+        // The last block moved: drop the gap that preceded it instead.
+        let kept = remaining.trim_end_matches(['\n', '\r']).len();
+        remaining.truncate(kept);
+        if kept > 0 && source.ends_with('\n') {
+            remaining.push('\n');
+        }
+    }
+
+    let mut target = read_note_content(&to_path)?;
+    let trimmed = target.trim_end_matches(['\n', '\r']).len();
+    target.truncate(trimmed);
+    if !target.is_empty() {
+        target.push_str("\n\n");
+    }
+    target.push_str(&block.text);
+    target.push('\n');
+
+    let mut txn = Transaction::new();
+    txn.write(&to_path, target);
+    txn.write(&from_path, remaining);
+    txn.commit()?;
+    cache::invalidate(&app, &sanitize_id(&from_id));
+    cache::invalidate(&app, &sanitize_id(&to_id));
+    links::invalidate(&app);
+    Ok(())
+}
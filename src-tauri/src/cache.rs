@@ -0,0 +1,106 @@
+// Read-through cache of the records `list_notes` returns. Each entry keeps
+// the file's modification time and size from when it was read; an entry
+// whose file no longer matches is re-read, so writes that bypass the explicit
+// invalidation (other apps, a missed watcher event) can't be served stale.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    time::SystemTime,
+};
+use tauri::Manager;
+
+use crate::{links, metadata, note_record, views, NoteRecord};
+
+struct CachedNote {
+    stamp: Stamp,
+    record: NoteRecord,
+}
+
+/// Modification time and size of a note file and of its metadata sidecar,
+/// which the record also reads.
+type Stamp = ((SystemTime, u64), Option<(SystemTime, u64)>);
+
+/// Note files mapped to their last read record.
+#[derive(Default)]
+pub struct NoteCache(Mutex<HashMap<PathBuf, CachedNote>>);
+
+fn file_stamp(meta: fs::Metadata) -> (SystemTime, u64) {
+    let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+    (modified, meta.len())
+}
+
+fn stamp(path: &Path) -> Result<Stamp, String> {
+    let note = fs::metadata(path)
+        .map_err(|e| format!("Failed to read file metadata ({}): {e}", path.display()))?;
+    let sidecar = fs::metadata(metadata::sidecar_path(path)).ok();
+    Ok((file_stamp(note), sidecar.map(file_stamp)))
+}
+
+/// Records for `files`, read from disk only where the cache has nothing
+/// current. Entries for files not in `files` are dropped.
+pub fn records(
+    app: &tauri::AppHandle,
+    files: Vec<(String, PathBuf)>,
+    views: &views::Views,
+) -> Result<Vec<NoteRecord>, String> {
+    let cache = app.state::<NoteCache>();
+    let storage = metadata::active(app)?;
+    let mut cached = cache.0.lock().unwrap_or_else(PoisonError::into_inner);
+    let listed: HashSet<&PathBuf> = files.iter().map(|(_, file)| file).collect();
+    cached.retain(|path, _| listed.contains(path));
+
+    let mut records = Vec::with_capacity(files.len());
+    for (id, path) in files {
+        let stamp = stamp(&path)?;
+        let hit = cached
+            .get(&path)
+            .filter(|entry| entry.stamp == stamp && entry.record.id == id);
+        let mut record = match hit {
+            Some(entry) => entry.record.clone(),
+            None => {
+                let record = note_record(id.clone(), &path, views, storage)?;
+                cached.insert(
+                    path,
+                    CachedNote {
+                        stamp,
+                        record: record.clone(),
+                    },
+                );
+                record
+            }
+        };
+        // View times live outside the file, so they're never cached.
+        record.last_viewed_ms = views.get(&id).copied();
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Drops the cached record of note `id`, wherever its file is.
+pub fn invalidate(app: &tauri::AppHandle, id: &str) {
+    let cache = app.state::<NoteCache>();
+    let mut cached = cache.0.lock().unwrap_or_else(PoisonError::into_inner);
+    cached.retain(|_, entry| entry.record.id != id);
+}
+
+/// Drops every cached record, for commands that change many notes at once.
+pub fn clear(app: &tauri::AppHandle) {
+    let cache = app.state::<NoteCache>();
+    cache
+        .0
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clear();
+}
+
+/// Empties the note record cache and the link matrix; the next calls read
+/// everything from disk again.
+#[tauri::command]
+pub fn clear_cache(app: tauri::AppHandle) -> Result<(), String> {
+    clear(&app);
+    links::invalidate(&app);
+    Ok(())
+}
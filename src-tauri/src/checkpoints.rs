@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use tauri::Manager;
+
+use crate::{
+    cache, confirm, ensure_notes_dir, frontmatter, fsutil, library_notes, links, read_note_content,
+    sanitize_id, sidecar, system_time_ms, trash, tree, watcher, TRASH_DIR,
+};
+
+/// Note id -> the text of its frontmatter block, `None` for notes without one.
+type FrontmatterSnapshot = BTreeMap<String, Option<String>>;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    name: String,
+    created_ms: u64,
+    /// Notes contained in the checkpoint.
+    notes: usize,
+}
+
+fn checkpoints_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let base = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {e}"))?;
+    Ok(base.join("checkpoints"))
+}
+
+fn checkpoint_name(raw: &str) -> Result<String, String> {
+    if raw.trim().is_empty() {
+        return Err("Checkpoint name must not be empty".to_string());
+    }
+    Ok(sanitize_id(raw.trim()))
+}
+
+/// Copies the whole library except the trash to `checkpoints/<name>/`.
+#[tauri::command]
+pub fn create_checkpoint(app: tauri::AppHandle, name: String) -> Result<Checkpoint, String> {
+    let name = checkpoint_name(&name)?;
+    let dir = ensure_notes_dir(&app)?;
+    let target = checkpoints_dir(&app)?.join(&name);
+    if target.exists() {
+        return Err(format!("Checkpoint already exists: {name}"));
+    }
+
+    if let Err(e) = fsutil::copy_dir_excluding(&dir, &target, &[TRASH_DIR]) {
+        // Don't leave a half-written checkpoint that looks complete.
+        let _ = fs::remove_dir_all(&target);
+        return Err(e);
+    }
+
+    Ok(Checkpoint {
+        name,
+        created_ms: system_time_ms(std::time::SystemTime::now()),
+        notes: tree::nested_notes(&target)?.len(),
+    })
+}
+
+#[tauri::command]
+pub fn list_checkpoints(app: tauri::AppHandle) -> Result<Vec<Checkpoint>, String> {
+    let root = checkpoints_dir(&app)?;
+    if !root.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut checkpoints = Vec::new();
+    let entries = fs::read_dir(&root).map_err(|e| format!("Failed to read checkpoints: {e}"))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read checkpoints: {e}"))?;
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let created_ms = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .map(system_time_ms)
+            .unwrap_or(0);
+        checkpoints.push(Checkpoint {
+            name: entry.file_name().to_string_lossy().to_string(),
+            created_ms,
+            notes: tree::nested_notes(&path)?.len(),
+        });
+    }
+    checkpoints.sort_by(|a, b| b.created_ms.cmp(&a.created_ms));
+    Ok(checkpoints)
+}
+
+/// Replaces the library with a checkpoint. Every current note is moved to the
+/// trash first, so the state before the restore can still be recovered.
+#[tauri::command]
+pub fn restore_checkpoint(
+    app: tauri::AppHandle,
+    name: String,
+    confirmed: bool,
+) -> Result<(), String> {
+    confirm::require_confirmation(&app, "restore_checkpoint", confirmed)?;
+    let name = checkpoint_name(&name)?;
+    let source = checkpoints_dir(&app)?.join(&name);
+    if !source.is_dir() {
+        return Err(format!("Checkpoint not found: {name}"));
+    }
+
+    restore_snapshot(&app, &source)
+}
+
+/// Trashes every current note, then copies the snapshot at `source` (a
+/// checkpoint or backup) into the notes dir.
+pub fn restore_snapshot(app: &tauri::AppHandle, source: &Path) -> Result<(), String> {
+    let dir = ensure_notes_dir(app)?;
+    let _paused = watcher::pause(app);
+    for (id, _) in library_notes(app, &dir)? {
+        trash::trash_note(&dir, &id)?;
+    }
+    fsutil::copy_dir(source, &dir)?;
+
+    cache::clear(app);
+    links::invalidate(app);
+    Ok(())
+}
+
+fn frontmatter_snapshot_path(app: &tauri::AppHandle, raw: &str) -> Result<PathBuf, String> {
+    if raw.trim().is_empty() {
+        return Err("Snapshot name must not be empty".to_string());
+    }
+    let base = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {e}"))?;
+    Ok(base
+        .join("frontmatter_snapshots")
+        .join(format!("{}.json", sanitize_id(raw.trim()))))
+}
+
+/// Saves the frontmatter block of every note under `name`, replacing an
+/// earlier snapshot of that name. Returns the number of notes saved.
+#[tauri::command]
+pub fn snapshot_frontmatter(app: tauri::AppHandle, name: String) -> Result<usize, String> {
+    let path = frontmatter_snapshot_path(&app, &name)?;
+    let dir = ensure_notes_dir(&app)?;
+
+    let mut snapshot = FrontmatterSnapshot::new();
+    for (id, note) in library_notes(&app, &dir)? {
+        let content = read_note_content(&note)?;
+        snapshot.insert(id, frontmatter::split(&content).0.map(str::to_string));
+    }
+    sidecar::save(&path, &snapshot)?;
+    Ok(snapshot.len())
+}
+
+/// Puts back the frontmatter saved by [`snapshot_frontmatter`], leaving
+/// bodies as they are now. Notes created since are left alone; ones deleted
+/// since are skipped. Returns the number of notes changed.
+#[tauri::command]
+pub fn restore_frontmatter(app: tauri::AppHandle, name: String) -> Result<usize, String> {
+    let path = frontmatter_snapshot_path(&app, &name)?;
+    if !path.is_file() {
+        return Err(format!("Snapshot not found: {}", name.trim()));
+    }
+    let snapshot: FrontmatterSnapshot = sidecar::load(&path)?;
+    let dir = ensure_notes_dir(&app)?;
+
+    let _paused = watcher::pause(&app);
+    let mut changed = 0usize;
+    for (id, note) in library_notes(&app, &dir)? {
+        let Some(fm) = snapshot.get(&id) else {
+            continue;
+        };
+        let content = read_note_content(&note)?;
+        let restored = frontmatter::replace_block(&content, fm.as_deref());
+        if restored != content {
+            fs::write(&note, restored).map_err(|e| format!("Failed to write note file: {e}"))?;
+            changed += 1;
+        }
+    }
+    if changed > 0 {
+        cache::clear(&app);
+        links::invalidate(&app);
+    }
+    Ok(changed)
+}
@@ -0,0 +1,325 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, fs, path::Path};
+
+use crate::{
+    cache, ensure_notes_dir, existing_note_path, frontmatter, library_notes, links,
+    markdown::is_fence, read_note_content, sanitize_id, watcher,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+/// Line endings found in a file, for diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEndingStyle {
+    /// A single line without any line break.
+    None,
+    Lf,
+    Crlf,
+    Mixed,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransformResult {
+    id: String,
+    /// Matches of the pattern in the note's body outside code fences.
+    matches: usize,
+    /// Whether the note was (or, in a dry run, would be) rewritten.
+    changed: bool,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EncodingInfo {
+    bytes: usize,
+    valid_utf8: bool,
+    /// Starts with the UTF-8 byte order mark (EF BB BF).
+    has_bom: bool,
+    line_endings: LineEndingStyle,
+    /// Characters outside ASCII. For invalid UTF-8 every undecodable
+    /// sequence counts as one.
+    non_ascii_chars: usize,
+}
+
+fn normalize(content: &str, style: LineEnding) -> String {
+    // Only the newline bytes change: a note that ended with a newline still
+    // does, and one that didn't is not given one.
+    let lf = content.replace("\r\n", "\n");
+    match style {
+        LineEnding::Lf => lf,
+        LineEnding::Crlf => lf.replace('\n', "\r\n"),
+    }
+}
+
+/// Rewrites `path` in the requested style. Returns whether the file changed.
+fn normalize_file(path: &Path, style: LineEnding) -> Result<bool, String> {
+    let content = read_note_content(path)?;
+
+    let normalized = normalize(&content, style);
+    if normalized == content {
+        return Ok(false);
+    }
+
+    fs::write(path, normalized).map_err(|e| format!("Failed to write note file: {e}"))?;
+    Ok(true)
+}
+
+fn trim_trailing(content: &str) -> String {
+    // Each line keeps its own ending: converting mixed endings is left to
+    // `normalize_line_endings`.
+    let mut in_fence = false;
+    let mut lines: Vec<(&str, &str)> = content
+        .split_inclusive('\n')
+        .map(|raw| {
+            let body = raw
+                .strip_suffix('\n')
+                .map_or(raw, |line| line.strip_suffix('\r').unwrap_or(line));
+            let ending = &raw[body.len()..];
+            // Fenced code keeps its trailing whitespace: it can be significant
+            // there (e.g. two-space line breaks inside a markdown sample).
+            let fence = is_fence(body);
+            in_fence ^= fence;
+            if fence || in_fence {
+                (body, ending)
+            } else {
+                (body.trim_end(), ending)
+            }
+        })
+        .collect();
+    while lines.last().is_some_and(|(body, _)| body.is_empty()) {
+        lines.pop();
+    }
+    // Dropping blank lines must not add a final newline the file never had.
+    if !content.ends_with('\n') {
+        if let Some((_, ending)) = lines.last_mut() {
+            *ending = "";
+        }
+    }
+
+    lines
+        .into_iter()
+        .flat_map(|(body, ending)| [body, ending])
+        .collect()
+}
+
+/// Trims `path` in place. Returns whether the file changed.
+fn trim_file(path: &Path) -> Result<bool, String> {
+    let content = read_note_content(path)?;
+
+    let trimmed = trim_trailing(&content);
+    if trimmed == content {
+        return Ok(false);
+    }
+
+    fs::write(path, trimmed).map_err(|e| format!("Failed to write note file: {e}"))?;
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn normalize_line_endings(
+    app: tauri::AppHandle,
+    id: String,
+    style: LineEnding,
+) -> Result<bool, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    let changed = normalize_file(&path, style)?;
+    if changed {
+        cache::invalidate(&app, &sanitize_id(&id));
+    }
+    Ok(changed)
+}
+
+#[tauri::command]
+pub fn normalize_all(app: tauri::AppHandle, style: LineEnding) -> Result<usize, String> {
+    let dir = ensure_notes_dir(&app)?;
+
+    let mut changed = 0usize;
+    for (_, path) in library_notes(&app, &dir)? {
+        if normalize_file(&path, style)? {
+            changed += 1;
+        }
+    }
+    if changed > 0 {
+        cache::clear(&app);
+    }
+    Ok(changed)
+}
+
+#[tauri::command]
+pub fn trim_whitespace(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    let changed = trim_file(&path)?;
+    if changed {
+        cache::invalidate(&app, &sanitize_id(&id));
+    }
+    Ok(changed)
+}
+
+#[tauri::command]
+pub fn trim_whitespace_all(app: tauri::AppHandle) -> Result<usize, String> {
+    let dir = ensure_notes_dir(&app)?;
+
+    let mut changed = 0usize;
+    for (_, path) in library_notes(&app, &dir)? {
+        if trim_file(&path)? {
+            changed += 1;
+        }
+    }
+    if changed > 0 {
+        cache::clear(&app);
+    }
+    Ok(changed)
+}
+
+fn line_ending_style(bytes: &[u8]) -> LineEndingStyle {
+    let mut lf = 0usize;
+    let mut crlf = 0usize;
+    for (i, &byte) in bytes.iter().enumerate() {
+        if byte == b'\n' {
+            if i > 0 && bytes[i - 1] == b'\r' {
+                crlf += 1;
+            } else {
+                lf += 1;
+            }
+        }
+    }
+    match (lf, crlf) {
+        (0, 0) => LineEndingStyle::None,
+        (_, 0) => LineEndingStyle::Lf,
+        (0, _) => LineEndingStyle::Crlf,
+        _ => LineEndingStyle::Mixed,
+    }
+}
+
+/// Byte-level diagnostics for a note; works on files that aren't valid UTF-8.
+#[tauri::command]
+pub fn note_encoding_info(app: tauri::AppHandle, id: String) -> Result<EncodingInfo, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read note file: {e}"))?;
+
+    let text = String::from_utf8_lossy(&bytes);
+    Ok(EncodingInfo {
+        bytes: bytes.len(),
+        valid_utf8: matches!(text, Cow::Borrowed(_)),
+        has_bom: bytes.starts_with(&[0xEF, 0xBB, 0xBF]),
+        line_endings: line_ending_style(&bytes),
+        non_ascii_chars: text.chars().filter(|c| !c.is_ascii()).count(),
+    })
+}
+
+/// Applies `pattern` to the text between code fences of `body`, one run of
+/// consecutive lines at a time so patterns can span lines but never reach
+/// into a fence. Returns the new body and the number of matches.
+fn transform_body(body: &str, pattern: &Regex, replacement: &str) -> (String, usize) {
+    let mut out = String::with_capacity(body.len());
+    let mut matches = 0usize;
+    let mut run = String::new();
+    let mut flush = |run: &mut String, out: &mut String| {
+        matches += pattern.find_iter(run).count();
+        out.push_str(&pattern.replace_all(run, replacement));
+        run.clear();
+    };
+
+    let mut in_fence = false;
+    for line in body.split_inclusive('\n') {
+        let fence = is_fence(line);
+        if fence {
+            in_fence = !in_fence;
+        }
+        if fence || in_fence {
+            flush(&mut run, &mut out);
+            out.push_str(line);
+        } else {
+            run.push_str(line);
+        }
+    }
+    flush(&mut run, &mut out);
+    (out, matches)
+}
+
+fn transform_note(
+    path: &Path,
+    pattern: &Regex,
+    replacement: &str,
+    dry_run: bool,
+) -> Result<(usize, bool), String> {
+    let content = read_note_content(path)?;
+    let body = frontmatter::body(&content);
+    let (transformed, matches) = transform_body(body, pattern, replacement);
+    if transformed == body {
+        return Ok((matches, false));
+    }
+
+    if !dry_run {
+        let updated = format!("{}{transformed}", &content[..content.len() - body.len()]);
+        fs::write(path, updated).map_err(|e| format!("Failed to write note file: {e}"))?;
+    }
+    Ok((matches, true))
+}
+
+/// Regex search and replace over note bodies (frontmatter and code fences are
+/// left alone). `replacement` may use `$1`/`${name}` capture references. With
+/// `ids` unset every note is transformed; with `dry_run` nothing is written.
+/// One note failing doesn't stop the others.
+#[tauri::command]
+pub fn regex_transform(
+    app: tauri::AppHandle,
+    pattern: String,
+    replacement: String,
+    ids: Option<Vec<String>>,
+    dry_run: bool,
+) -> Result<Vec<TransformResult>, String> {
+    let pattern = Regex::new(&pattern).map_err(|e| format!("Invalid pattern: {e}"))?;
+    let dir = ensure_notes_dir(&app)?;
+    let targets = match ids {
+        Some(ids) => ids
+            .into_iter()
+            .map(|id| {
+                let path = existing_note_path(&dir, &id);
+                (id, path)
+            })
+            .collect::<Vec<_>>(),
+        None => {
+            let mut notes = library_notes(&app, &dir)?;
+            notes.sort();
+            notes
+                .into_iter()
+                .map(|(id, path)| (id, Ok(path)))
+                .collect::<Vec<_>>()
+        }
+    };
+
+    let _paused = (!dry_run).then(|| watcher::pause(&app));
+    let mut results = Vec::new();
+    for (id, path) in targets {
+        let result = path.and_then(|path| transform_note(&path, &pattern, &replacement, dry_run));
+        results.push(match result {
+            Ok((matches, changed)) => TransformResult {
+                id,
+                matches,
+                changed,
+                error: None,
+            },
+            Err(e) => TransformResult {
+                id,
+                matches: 0,
+                changed: false,
+                error: Some(e),
+            },
+        });
+    }
+    if !dry_run && results.iter().any(|r| r.changed) {
+        cache::clear(&app);
+        links::invalidate(&app);
+    }
+    Ok(results)
+}
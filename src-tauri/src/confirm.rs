@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+use crate::settings;
+
+/// Commands that refuse to run unless called with `confirmed: true`. Every
+/// destructive command is listed by default; removing one from the list in
+/// settings lets it run without the extra round trip.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DangerousOperations(pub Vec<String>);
+
+impl Default for DangerousOperations {
+    fn default() -> Self {
+        Self(
+            [
+                "delete_notes",
+                "empty_trash",
+                "restore_backup",
+                "restore_checkpoint",
+                "purge_note",
+            ]
+            .map(String::from)
+            .to_vec(),
+        )
+    }
+}
+
+/// Fails with "Confirmation required" when `operation` is listed as dangerous
+/// and the caller didn't confirm it.
+pub fn require_confirmation(
+    app: &tauri::AppHandle,
+    operation: &str,
+    confirmed: bool,
+) -> Result<(), String> {
+    if confirmed {
+        return Ok(());
+    }
+    let settings = settings::load(app)?;
+    if settings
+        .dangerous_operations
+        .0
+        .iter()
+        .any(|op| op == operation)
+    {
+        return Err("Confirmation required".to_string());
+    }
+    Ok(())
+}
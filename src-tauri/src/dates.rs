@@ -0,0 +1,68 @@
+// Calendar arithmetic on UTC days, enough for bucketing timestamps without
+// pulling in a date/time crate.
+
+pub const MS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+/// Days since 1970-01-01 (UTC) of a millisecond timestamp.
+pub fn day_of(ms: u64) -> i64 {
+    (ms / MS_PER_DAY) as i64
+}
+
+/// `(year, month, day)` of a day number, using Howard Hinnant's
+/// `civil_from_days`.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Day number of a calendar date; inverse of [`civil_from_days`].
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = i64::from(month);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Day number of a `YYYY-MM-DD` date, if it is one.
+pub fn parse_day(text: &str) -> Option<i64> {
+    let mut parts = text.trim().splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    // Rejects dates like 02-31 that would roll over into the next month.
+    (civil_from_days(days) == (year, month, day)).then_some(days)
+}
+
+/// `YYYY-MM-DD` of a day number.
+pub fn format_day(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// RFC 3339 UTC timestamp (`YYYY-MM-DDTHH:MM:SSZ`) of a millisecond time.
+pub fn format_rfc3339(ms: u64) -> String {
+    let secs = (ms / 1000) % (24 * 60 * 60);
+    format!(
+        "{}T{:02}:{:02}:{:02}Z",
+        format_day(day_of(ms)),
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{ensure_notes_dir, existing_note_path, pin, read_note_content};
+
+// Above this many cells the LCS table gets too big to be worth it; the
+// changed middle is then reported as one removal followed by one addition.
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    Same,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+/// Line diff turning `old` into `new`.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    diff_slices(&old, &new)
+        .into_iter()
+        .map(|(kind, text)| DiffLine {
+            kind,
+            text: text.to_string(),
+        })
+        .collect()
+}
+
+/// [`diff_lines`] over lines the caller already split, e.g. with their line
+/// endings kept.
+pub fn diff_slices<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(DiffKind, &'a str)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let mut out: Vec<(DiffKind, &str)> = old[..prefix]
+        .iter()
+        .map(|text| (DiffKind::Same, *text))
+        .collect();
+
+    if a.len().saturating_mul(b.len()) > MAX_LCS_CELLS {
+        out.extend(a.iter().map(|text| (DiffKind::Removed, *text)));
+        out.extend(b.iter().map(|text| (DiffKind::Added, *text)));
+    } else {
+        // lcs[i][j] = length of the LCS of a[i..] and b[j..].
+        let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i][j] = if a[i] == b[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            if a[i] == b[j] {
+                out.push((DiffKind::Same, a[i]));
+                i += 1;
+                j += 1;
+            } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+                out.push((DiffKind::Removed, a[i]));
+                i += 1;
+            } else {
+                out.push((DiffKind::Added, b[j]));
+                j += 1;
+            }
+        }
+        out.extend(a[i..].iter().map(|text| (DiffKind::Removed, *text)));
+        out.extend(b[j..].iter().map(|text| (DiffKind::Added, *text)));
+    }
+
+    out.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|text| (DiffKind::Same, *text)),
+    );
+    out
+}
+
+/// What saving `proposed` would change compared to the file on disk.
+/// Nothing is written.
+#[tauri::command]
+pub fn diff_against_disk(
+    app: tauri::AppHandle,
+    id: String,
+    proposed: String,
+) -> Result<Vec<DiffLine>, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    Ok(diff_lines(&read_note_content(&path)?, &proposed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The two sides of a diff: `old` is every line but the added ones, `new`
+    /// every line but the removed ones.
+    fn sides(diff: &[DiffLine]) -> (Vec<&str>, Vec<&str>) {
+        let side = |skip: DiffKind| -> Vec<&str> {
+            diff.iter()
+                .filter(|line| line.kind != skip)
+                .map(|line| line.text.as_str())
+                .collect()
+        };
+        (side(DiffKind::Added), side(DiffKind::Removed))
+    }
+
+    #[test]
+    fn diff_covers_both_texts() {
+        let old = "title\nkeep\ndrop\nkeep too\nend\n";
+        let new = "title\nkeep\nkeep too\nadded\nend\n";
+        let diff = diff_lines(old, new);
+        let (before, after) = sides(&diff);
+        assert_eq!(before, old.lines().collect::<Vec<_>>());
+        assert_eq!(after, new.lines().collect::<Vec<_>>());
+        let changed: Vec<(DiffKind, &str)> = diff
+            .iter()
+            .filter(|line| line.kind != DiffKind::Same)
+            .map(|line| (line.kind, line.text.as_str()))
+            .collect();
+        assert_eq!(
+            changed,
+            [(DiffKind::Removed, "drop"), (DiffKind::Added, "added")]
+        );
+    }
+
+    #[test]
+    fn identical_texts_are_all_same() {
+        let diff = diff_slices(&["a\n", "b\n"], &["a\n", "b\n"]);
+        assert!(diff.iter().all(|(kind, _)| *kind == DiffKind::Same));
+        assert_eq!(diff.len(), 2);
+    }
+}
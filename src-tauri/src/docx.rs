@@ -0,0 +1,226 @@
+// Word export. Headings, paragraphs, emphasis, lists, quotes and code map to
+// Word styles; anything else (tables, images, HTML) degrades to its text in
+// plain paragraphs.
+
+use docx_rs::{BreakType, Docx, Paragraph, Run, RunFonts, Style, StyleType};
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::{fs::File, path::PathBuf};
+
+use crate::{
+    ensure_notes_dir, existing_note_path, frontmatter, links, metadata, note_title, pin,
+    sanitize_id,
+};
+
+const CODE_FONT: &str = "Courier New";
+// Word measures indents in twentieths of a point: 720 is half an inch.
+const INDENT_STEP: i32 = 720;
+
+fn styles(mut docx: Docx) -> Docx {
+    // Sizes are in half-points.
+    docx = docx.add_style(
+        Style::new("Title", StyleType::Paragraph)
+            .name("Title")
+            .size(52)
+            .bold(),
+    );
+    for (level, size) in [(1, 36), (2, 30), (3, 26), (4, 24), (5, 22), (6, 22)] {
+        docx = docx.add_style(
+            Style::new(format!("Heading{level}"), StyleType::Paragraph)
+                .name(format!("Heading {level}"))
+                .size(size)
+                .bold(),
+        );
+    }
+    docx.add_style(
+        Style::new("Quote", StyleType::Paragraph)
+            .name("Quote")
+            .italic(),
+    )
+    .add_style(
+        Style::new("Code", StyleType::Paragraph)
+            .name("Code")
+            .size(20),
+    )
+    .add_style(Style::new("ListParagraph", StyleType::Paragraph).name("List Paragraph"))
+}
+
+#[derive(Default)]
+struct Writer {
+    paragraphs: Vec<Paragraph>,
+    current: Option<Paragraph>,
+    bold: bool,
+    italic: bool,
+    /// Open lists, innermost last: the next item number, `None` for bullets.
+    lists: Vec<Option<u64>>,
+    quote_depth: usize,
+    code_block: bool,
+}
+
+impl Writer {
+    fn start(&mut self, style: &str) {
+        self.finish();
+        let mut paragraph = Paragraph::new().style(style);
+        let depth = self.lists.len() + self.quote_depth;
+        if depth > 0 {
+            paragraph = paragraph.indent(Some(INDENT_STEP * depth as i32), None, None, None);
+        }
+        self.current = Some(paragraph);
+    }
+
+    fn finish(&mut self) {
+        if let Some(paragraph) = self.current.take() {
+            self.paragraphs.push(paragraph);
+        }
+    }
+
+    fn body_style(&self) -> &'static str {
+        if self.quote_depth > 0 {
+            "Quote"
+        } else {
+            "Normal"
+        }
+    }
+
+    fn push_run(&mut self, run: Run) {
+        if self.current.is_none() {
+            self.start(self.body_style());
+        }
+        self.current = self.current.take().map(|p| p.add_run(run));
+    }
+
+    fn text(&mut self, text: &str, code: bool) {
+        let mut run = Run::new().add_text(text);
+        if self.bold {
+            run = run.bold();
+        }
+        if self.italic {
+            run = run.italic();
+        }
+        if code {
+            run = run.fonts(RunFonts::new().ascii(CODE_FONT).hi_ansi(CODE_FONT));
+        }
+        self.push_run(run);
+    }
+
+    fn code_lines(&mut self, text: &str) {
+        // One paragraph per line keeps the line breaks and indentation.
+        for line in text.lines() {
+            self.start("Code");
+            self.text(line, true);
+        }
+        self.finish();
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                let level = match level {
+                    HeadingLevel::H1 => 1,
+                    HeadingLevel::H2 => 2,
+                    HeadingLevel::H3 => 3,
+                    HeadingLevel::H4 => 4,
+                    HeadingLevel::H5 => 5,
+                    HeadingLevel::H6 => 6,
+                };
+                self.start(&format!("Heading{level}"));
+            }
+            // List items already opened their paragraph.
+            Event::Start(Tag::Paragraph) if self.current.is_none() || self.lists.is_empty() => {
+                self.start(self.body_style());
+            }
+            Event::Start(Tag::BlockQuote(_)) => {
+                self.finish();
+                self.quote_depth += 1;
+            }
+            Event::End(TagEnd::BlockQuote(_)) => {
+                self.finish();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+            }
+            Event::Start(Tag::List(first)) => {
+                self.finish();
+                self.lists.push(first);
+            }
+            Event::End(TagEnd::List(_)) => {
+                self.finish();
+                self.lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                self.start("ListParagraph");
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        let marker = format!("{n}. ");
+                        *n += 1;
+                        marker
+                    }
+                    _ => "\u{2022} ".to_string(),
+                };
+                self.push_run(Run::new().add_text(marker));
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                self.finish();
+                self.code_block = true;
+            }
+            Event::End(TagEnd::CodeBlock) => self.code_block = false,
+            Event::Start(Tag::Strong) => self.bold = true,
+            Event::End(TagEnd::Strong) => self.bold = false,
+            Event::Start(Tag::Emphasis) => self.italic = true,
+            Event::End(TagEnd::Emphasis) => self.italic = false,
+            Event::Text(text) if self.code_block => self.code_lines(&text),
+            Event::Text(text) => self.text(&text, false),
+            Event::Code(code) => self.text(&code, true),
+            Event::SoftBreak => self.text(" ", false),
+            Event::HardBreak => self.push_run(Run::new().add_break(BreakType::TextWrapping)),
+            Event::End(
+                TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item | TagEnd::TableRow,
+            )
+            | Event::Rule => self.finish(),
+            // Table cells of a row share its paragraph.
+            Event::End(TagEnd::TableCell) => self.text("\t", false),
+            _ => {}
+        }
+    }
+}
+
+/// Writes the note as a Word document to `dest` (a file, or a folder to put
+/// `<id>.docx` in). The title becomes the document's title paragraph and
+/// frontmatter is left out. Returns the path written.
+#[tauri::command]
+pub fn export_note_docx(app: tauri::AppHandle, id: String, dest: String) -> Result<String, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    let id = sanitize_id(&id);
+    let content = metadata::read_view(metadata::active(&app)?, &path)?;
+
+    // Wiki links read as their label; there's nothing in the document to
+    // link to.
+    let body = links::replace_links(frontmatter::body(&content), |_, inner| {
+        let label = inner.split_once('|').map_or(inner, |(_, alias)| alias);
+        Some(label.trim().to_string())
+    });
+
+    let mut writer = Writer::default();
+    writer.start("Title");
+    writer.text(&note_title(&id, &content), false);
+    writer.finish();
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH;
+    for event in Parser::new_ext(&body, options) {
+        writer.event(event);
+    }
+    writer.finish();
+
+    let docx = writer
+        .paragraphs
+        .into_iter()
+        .fold(styles(Docx::new()), Docx::add_paragraph);
+
+    let mut dest = PathBuf::from(dest);
+    if dest.is_dir() {
+        dest = dest.join(format!("{id}.docx"));
+    }
+    let file = File::create(&dest).map_err(|e| format!("Failed to create DOCX file: {e}"))?;
+    docx.build()
+        .pack(file)
+        .map_err(|e| format!("Failed to write DOCX file: {e}"))?;
+    Ok(dest.to_string_lossy().to_string())
+}
@@ -0,0 +1,476 @@
+// Evernote `.enex` import. An ENEX file is XML holding one `<note>` per note;
+// its `<content>` is ENML (a restricted XHTML) that is converted to markdown
+// here, and its `<resource>`s are base64 attachments referenced from the ENML
+// by MD5 hash.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use md5::{Digest, Md5};
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{
+    cache, ensure_notes_dir, frontmatter, generate_id,
+    import::{write_new_note, ImportSummary},
+    links, metadata, note_path, watcher, ATTACHMENTS_DIR,
+};
+
+#[derive(Default)]
+struct EnexResource {
+    data: String,
+    mime: String,
+    file_name: Option<String>,
+}
+
+#[derive(Default)]
+struct EnexNote {
+    title: String,
+    content: String,
+    tags: Vec<String>,
+    created: Option<String>,
+    resources: Vec<EnexResource>,
+}
+
+fn parse_enex(path: &Path) -> Result<Vec<EnexNote>, String> {
+    let mut reader =
+        Reader::from_file(path).map_err(|e| format!("Failed to open ENEX file: {e}"))?;
+    let mut notes = Vec::new();
+    let mut note: Option<EnexNote> = None;
+    let mut stack: Vec<String> = Vec::new();
+    let mut buf = Vec::new();
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| format!("Invalid ENEX file: {e}"))?;
+        let text = match event {
+            Event::Start(e) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                match (name.as_str(), note.as_mut()) {
+                    ("note", _) => note = Some(EnexNote::default()),
+                    ("resource", Some(note)) => note.resources.push(EnexResource::default()),
+                    ("tag", Some(note)) => note.tags.push(String::new()),
+                    _ => {}
+                }
+                stack.push(name);
+                None
+            }
+            Event::End(_) => {
+                if stack.pop().as_deref() == Some("note") {
+                    notes.extend(note.take());
+                }
+                None
+            }
+            Event::Text(e) => Some(
+                e.unescape()
+                    .map_err(|e| format!("Invalid ENEX file: {e}"))?
+                    .into_owned(),
+            ),
+            Event::CData(e) => Some(String::from_utf8_lossy(&e.into_inner()).into_owned()),
+            Event::Eof => break,
+            _ => None,
+        };
+
+        if let (Some(text), Some(note)) = (text, note.as_mut()) {
+            let resource = note.resources.last_mut();
+            match (stack.last().map(String::as_str), resource) {
+                (Some("title"), _) => note.title.push_str(&text),
+                (Some("content"), _) => note.content.push_str(&text),
+                (Some("tag"), _) => {
+                    if let Some(tag) = note.tags.last_mut() {
+                        tag.push_str(&text);
+                    }
+                }
+                (Some("created"), _) => note.created = Some(text.trim().to_string()),
+                (Some("data"), Some(resource)) => resource.data.push_str(&text),
+                (Some("mime"), Some(resource)) => resource.mime = text.trim().to_string(),
+                (Some("file-name"), Some(resource)) => {
+                    resource.file_name = Some(text.trim().to_string())
+                }
+                _ => {}
+            }
+        }
+        buf.clear();
+    }
+    Ok(notes)
+}
+
+/// Decodes the XML entities ENML text may contain, including the HTML ones
+/// Evernote writes (`&nbsp;`) that a plain XML unescape rejects.
+fn decode_entities(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let decoded = after.find(';').and_then(|end| {
+            let entity = &after[..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16).ok())
+                    .unwrap_or_else(|| entity.strip_prefix('#').and_then(|d| d.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn attr(e: &BytesStart, name: &str) -> Option<String> {
+    e.attributes()
+        .filter_map(Result::ok)
+        .find(|a| a.key.as_ref() == name.as_bytes())
+        .map(|a| decode_entities(&String::from_utf8_lossy(&a.value)))
+}
+
+/// Streaming ENML-to-markdown conversion. Only the structure ENML actually
+/// produces is handled; anything else contributes just its text.
+struct Converter<'a> {
+    out: String,
+    /// Attachment path of each resource, keyed by MD5 hash.
+    media: &'a HashMap<String, String>,
+    /// Open lists, innermost last: `None` for bullets, `Some(n)` for the last
+    /// number used in an ordered list.
+    lists: Vec<Option<usize>>,
+    /// Output offset where each open blockquote started.
+    quotes: Vec<usize>,
+    links: Vec<Option<String>>,
+    in_pre: bool,
+}
+
+impl Converter<'_> {
+    fn newline(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn paragraph(&mut self) {
+        self.newline();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn text(&mut self, raw: &str) {
+        let text = decode_entities(raw);
+        if self.in_pre {
+            self.out.push_str(&text);
+            return;
+        }
+        let mut collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.starts_with(char::is_whitespace) && !collapsed.is_empty() {
+            collapsed.insert(0, ' ');
+        }
+        if text.ends_with(char::is_whitespace) && !collapsed.ends_with(' ') {
+            collapsed.push(' ');
+        }
+        if self.out.is_empty() || self.out.ends_with('\n') || self.out.ends_with(' ') {
+            collapsed = collapsed.trim_start().to_string();
+        }
+        self.out.push_str(&collapsed);
+    }
+
+    fn start(&mut self, e: &BytesStart) {
+        match e.name().as_ref() {
+            b"p" | b"table" => self.paragraph(),
+            b"div" | b"tr" => self.newline(),
+            b"br" => self.out.push('\n'),
+            b"hr" => {
+                self.paragraph();
+                self.out.push_str("---");
+                self.paragraph();
+            }
+            name @ (b"h1" | b"h2" | b"h3" | b"h4" | b"h5" | b"h6") => {
+                self.paragraph();
+                let level = usize::from(name[1] - b'0');
+                self.out.push_str(&format!("{} ", "#".repeat(level)));
+            }
+            b"b" | b"strong" => self.out.push_str("**"),
+            b"i" | b"em" => self.out.push('*'),
+            b"s" | b"strike" | b"del" => self.out.push_str("~~"),
+            b"code" if !self.in_pre => self.out.push('`'),
+            b"a" => {
+                let href = attr(e, "href");
+                if href.is_some() {
+                    self.out.push('[');
+                }
+                self.links.push(href);
+            }
+            b"ul" | b"ol" => {
+                if self.lists.is_empty() {
+                    self.paragraph();
+                } else {
+                    self.newline();
+                }
+                let ordered = e.name().as_ref() == b"ol";
+                self.lists.push(ordered.then_some(0));
+            }
+            b"li" => {
+                self.newline();
+                let indent = "  ".repeat(self.lists.len().saturating_sub(1));
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{n}. ")
+                    }
+                    _ => "- ".to_string(),
+                };
+                self.out.push_str(&indent);
+                self.out.push_str(&marker);
+            }
+            b"blockquote" => {
+                self.paragraph();
+                self.quotes.push(self.out.len());
+            }
+            b"pre" => {
+                self.paragraph();
+                self.out.push_str("```\n");
+                self.in_pre = true;
+            }
+            b"en-todo" => {
+                if self.lists.is_empty() {
+                    self.out.push_str("- ");
+                }
+                let checked = attr(e, "checked").is_some_and(|v| v == "true");
+                self.out.push_str(if checked { "[x] " } else { "[ ] " });
+            }
+            b"en-media" => {
+                let Some(path) = attr(e, "hash").and_then(|hash| self.media.get(&hash)) else {
+                    return;
+                };
+                let is_image = attr(e, "type").is_some_and(|t| t.starts_with("image/"));
+                let name = path.rsplit('/').next().unwrap_or(path);
+                if is_image {
+                    self.out.push_str(&format!("![{name}]({path})"));
+                } else {
+                    self.out.push_str(&format!("[{name}]({path})"));
+                }
+            }
+            b"img" => {
+                if let Some(src) = attr(e, "src") {
+                    self.out.push_str(&format!("![]({src})"));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, name: &[u8]) {
+        match name {
+            b"p" | b"table" | b"h1" | b"h2" | b"h3" | b"h4" | b"h5" | b"h6" => self.paragraph(),
+            b"div" | b"li" | b"tr" => self.newline(),
+            b"td" | b"th" => self.out.push_str(" | "),
+            b"b" | b"strong" => self.out.push_str("**"),
+            b"i" | b"em" => self.out.push('*'),
+            b"s" | b"strike" | b"del" => self.out.push_str("~~"),
+            b"code" if !self.in_pre => self.out.push('`'),
+            b"a" => {
+                if let Some(Some(href)) = self.links.pop() {
+                    self.out.push_str(&format!("]({href})"));
+                }
+            }
+            b"ul" | b"ol" => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.paragraph();
+                } else {
+                    self.newline();
+                }
+            }
+            b"blockquote" => {
+                let Some(start) = self.quotes.pop() else {
+                    return;
+                };
+                let quoted: String = self.out[start..]
+                    .trim_end()
+                    .lines()
+                    .map(|line| format!("> {line}\n"))
+                    .collect();
+                self.out.truncate(start);
+                self.out.push_str(&quoted);
+                self.paragraph();
+            }
+            b"pre" => {
+                self.newline();
+                self.out.push_str("```");
+                self.in_pre = false;
+                self.paragraph();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn enml_to_markdown(enml: &str, media: &HashMap<String, String>) -> Result<String, String> {
+    let mut reader = Reader::from_str(enml);
+    reader.config_mut().check_end_names = false;
+    let mut converter = Converter {
+        out: String::new(),
+        media,
+        lists: Vec::new(),
+        quotes: Vec::new(),
+        links: Vec::new(),
+        in_pre: false,
+    };
+
+    loop {
+        match reader
+            .read_event()
+            .map_err(|e| format!("invalid ENML: {e}"))?
+        {
+            Event::Start(e) => converter.start(&e),
+            Event::Empty(e) => {
+                converter.start(&e);
+                converter.end(e.name().as_ref());
+            }
+            Event::End(e) => converter.end(e.name().as_ref()),
+            Event::Text(e) => converter.text(&String::from_utf8_lossy(&e)),
+            Event::CData(e) => converter.text(&String::from_utf8_lossy(&e)),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let mut markdown = converter.out.trim().to_string();
+    while markdown.contains("\n\n\n") {
+        markdown = markdown.replace("\n\n\n", "\n\n");
+    }
+    markdown.push('\n');
+    Ok(markdown)
+}
+
+/// Writes the note's resources into the attachments folder and returns their
+/// relative paths keyed by MD5 hash. The hash prefix in each file name keeps
+/// same-named attachments of different notes apart.
+fn extract_resources(
+    dir: &Path,
+    resources: &[EnexResource],
+) -> Result<HashMap<String, String>, String> {
+    let mut media = HashMap::new();
+    for resource in resources {
+        let encoded: String = resource.data.split_whitespace().collect();
+        let data = STANDARD
+            .decode(encoded)
+            .map_err(|e| format!("invalid attachment data: {e}"))?;
+        let hash: String = Md5::digest(&data)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+
+        let name = match resource.file_name.as_deref().filter(|n| !n.is_empty()) {
+            // Spaces and brackets would break the markdown link to the file
+            // (and dedup and purge finding it), so they become `_` as well.
+            Some(name) => {
+                name.replace(|c: char| c.is_whitespace() || "/\\:()[]<>".contains(c), "_")
+            }
+            None => {
+                let ext = resource.mime.split('/').nth(1).unwrap_or("bin");
+                format!("attachment.{ext}")
+            }
+        };
+        let rel = format!("{ATTACHMENTS_DIR}/{}-{name}", &hash[..8]);
+
+        let path = dir.join(&rel);
+        if !path.exists() {
+            fs::create_dir_all(dir.join(ATTACHMENTS_DIR))
+                .map_err(|e| format!("failed to create attachments dir: {e}"))?;
+            fs::write(&path, &data).map_err(|e| format!("failed to write attachment: {e}"))?;
+        }
+        media.insert(hash, rel);
+    }
+    Ok(media)
+}
+
+/// `YYYY-MM-DD` of an ENEX timestamp like `20200131T094500Z`.
+fn enex_day(raw: &str) -> Option<String> {
+    let digits = raw
+        .get(..8)
+        .filter(|d| d.bytes().all(|b| b.is_ascii_digit()))?;
+    Some(format!(
+        "{}-{}-{}",
+        &digits[..4],
+        &digits[4..6],
+        &digits[6..]
+    ))
+}
+
+fn convert_note(dir: &Path, note: &EnexNote) -> Result<String, String> {
+    let media = extract_resources(dir, &note.resources)?;
+    let mut content = enml_to_markdown(&note.content, &media)?;
+
+    let title = note.title.trim();
+    if !title.is_empty() {
+        content = frontmatter::set_field(&content, "title", Some(title));
+    }
+    let tags: Vec<String> = note
+        .tags
+        .iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    if !tags.is_empty() {
+        content = frontmatter::set_raw(&content, "tags", Some(&frontmatter::inline_list(&tags)));
+    }
+    if let Some(day) = note.created.as_deref().and_then(enex_day) {
+        content = frontmatter::set_raw(&content, "created", Some(&day));
+    }
+    Ok(content)
+}
+
+/// Imports every note of an Evernote export. A note that can't be converted
+/// is reported in `failures` and the rest still go in.
+#[tauri::command]
+pub fn import_enex(app: tauri::AppHandle, source: String) -> Result<ImportSummary, String> {
+    let notes = parse_enex(Path::new(&source))?;
+    let dir = ensure_notes_dir(&app)?;
+    let storage = metadata::active(&app)?;
+    let _paused = watcher::pause(&app);
+
+    let mut summary = ImportSummary::default();
+    for (i, note) in notes.iter().enumerate() {
+        let label = match note.title.trim() {
+            "" => format!("note {}", i + 1),
+            title => title.to_string(),
+        };
+        let written = convert_note(&dir, note).and_then(|content| {
+            let title = Some(note.title.trim()).filter(|t| !t.is_empty());
+            let id = generate_id(&app, &dir, title)?;
+            let id = write_new_note(&dir, &id, &content)?;
+            metadata::adopt(storage, &note_path(&dir, &id))
+        });
+        match written {
+            Ok(()) => summary.created += 1,
+            Err(e) => {
+                summary.skipped += 1;
+                summary.failures.push(format!("{label}: {e}"));
+            }
+        }
+    }
+
+    cache::clear(&app);
+    links::invalidate(&app);
+    Ok(summary)
+}
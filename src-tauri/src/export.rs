@@ -0,0 +1,367 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    fs,
+    path::PathBuf,
+};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::{
+    ensure_notes_dir, existing_note_path, feed::xml_escape, file_times_ms, frontmatter,
+    library_notes, links, locate_note, metadata, note_files, note_title, permalink, pin,
+    read_note_content, render, sanitize_id, tags, ARCHIVE_DIR, TRASH_DIR,
+};
+
+/// Where a note lives inside the notes dir.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Location {
+    #[default]
+    Notes,
+    Archive,
+    Trash,
+}
+
+impl Location {
+    /// Subfolder of the notes dir holding notes in this location.
+    pub fn subdir(self) -> Option<&'static str> {
+        match self {
+            Location::Notes => None,
+            Location::Archive => Some(ARCHIVE_DIR),
+            Location::Trash => Some(TRASH_DIR),
+        }
+    }
+}
+
+/// One note in the JSON library format shared by `export_library_json` and
+/// `import_library_json`. `content` is the raw file (frontmatter included).
+/// With sidecar metadata storage the title, tags, pinned and color aren't in
+/// it, so an import fills in any of those the content lacks; the other
+/// fields are for consumers that don't want to parse markdown themselves.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LibraryNote {
+    pub id: String,
+    pub content: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub links: Vec<String>,
+    #[serde(default)]
+    pub created_ms: Option<u64>,
+    #[serde(default)]
+    pub modified_ms: Option<u64>,
+    #[serde(default)]
+    pub location: Location,
+}
+
+const LIBRARY_JSON_FILE: &str = "library.json";
+
+// Written next to the exported notes. The leading underscore keeps it from
+// clashing with a note that happens to be called `index`.
+const EXPORT_INDEX_FILE: &str = "_index.md";
+
+const SITE_INDEX_FILE: &str = "index.html";
+
+fn prepare_dest(dest: &str) -> Result<PathBuf, String> {
+    let dest = PathBuf::from(dest);
+    fs::create_dir_all(&dest).map_err(|e| format!("Failed to create export dir: {e}"))?;
+    Ok(dest)
+}
+
+#[tauri::command]
+pub fn export_tag_folder(
+    app: tauri::AppHandle,
+    tag: String,
+    dest: String,
+) -> Result<usize, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    let tag = tags::normalize_tag(&tag);
+    if tag.is_empty() {
+        return Err("Tag must not be empty".to_string());
+    }
+
+    let storage = metadata::active(&app)?;
+    let mut exported = Vec::new();
+    for (id, path) in library_notes(&app, &dir)? {
+        // Exported files carry their metadata, whatever the storage.
+        let content = metadata::read_view(storage, &path)?;
+        if tags::has_tag(&content, &tag) {
+            exported.push((id.clone(), note_title(&id, &content), content));
+        }
+    }
+
+    let dest = prepare_dest(&dest)?;
+    for (id, _, content) in &exported {
+        fs::write(dest.join(format!("{id}.md")), content)
+            .map_err(|e| format!("Failed to export note ({id}): {e}"))?;
+    }
+
+    exported.sort_by(|a, b| a.1.to_lowercase().cmp(&b.1.to_lowercase()));
+    let mut index = format!("# Notes tagged #{tag}\n\n");
+    for (id, title, _) in &exported {
+        index.push_str(&format!("- [{title}]({id}.md)\n"));
+    }
+    fs::write(dest.join(EXPORT_INDEX_FILE), index)
+        .map_err(|e| format!("Failed to write export index: {e}"))?;
+
+    Ok(exported.len())
+}
+
+#[tauri::command]
+pub fn copy_note_plaintext(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    let text = render::to_plaintext(&read_note_content(&path)?);
+
+    // An empty note still overwrites the clipboard, so stale content isn't
+    // pasted by mistake.
+    app.clipboard()
+        .write_text(text)
+        .map_err(|e| format!("Failed to write to clipboard: {e}"))
+}
+
+#[tauri::command]
+pub fn export_library_json(
+    app: tauri::AppHandle,
+    dest: String,
+    include_archived: Option<bool>,
+) -> Result<String, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+
+    let storage = metadata::active(&app)?;
+    let mut locations = vec![Location::Notes];
+    if include_archived.unwrap_or(false) {
+        locations.extend([Location::Archive, Location::Trash]);
+    }
+
+    let mut notes = Vec::new();
+    for location in locations {
+        let files = match location.subdir() {
+            Some(sub) if dir.join(sub).is_dir() => note_files(&dir.join(sub))?,
+            Some(_) => continue,
+            None => library_notes(&app, &dir)?,
+        };
+
+        for (id, path) in files {
+            let content = read_note_content(&path)?;
+            let shown = metadata::view(storage, &path, content.clone())?;
+            let (created_ms, modified_ms) = file_times_ms(&path)?;
+
+            notes.push(LibraryNote {
+                title: Some(note_title(&id, &shown)),
+                tags: tags::note_tags(&shown),
+                pinned: frontmatter::field(&shown, "pinned").is_some_and(|v| v == "true"),
+                color: frontmatter::field(&shown, "color"),
+                links: links::link_ids(&content),
+                created_ms: Some(created_ms),
+                modified_ms: Some(modified_ms),
+                location,
+                id,
+                content,
+            });
+        }
+    }
+    notes.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut dest = PathBuf::from(dest);
+    if dest.is_dir() {
+        dest = dest.join(LIBRARY_JSON_FILE);
+    } else if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create export dir: {e}"))?;
+    }
+
+    let raw = serde_json::to_string_pretty(&notes)
+        .map_err(|e| format!("Failed to serialize library: {e}"))?;
+    fs::write(&dest, raw).map_err(|e| format!("Failed to write library export: {e}"))?;
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Exports `id` and every note reachable from it within `depth` wiki-link hops.
+/// Links between bundled notes become relative markdown links; links leaving
+/// the bundle stay wiki links, and broken ones are listed in the index.
+#[tauri::command]
+pub fn export_linked_bundle(
+    app: tauri::AppHandle,
+    id: String,
+    depth: usize,
+    dest: String,
+) -> Result<usize, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    let root_path = existing_note_path(&dir, &id)?;
+    let root = sanitize_id(&id);
+    let storage = metadata::active(&app)?;
+
+    // Breadth-first so each note is reached by its shortest path.
+    let mut bundle: HashMap<String, String> = HashMap::new();
+    let mut broken: BTreeSet<(String, String)> = BTreeSet::new();
+    let mut queue = VecDeque::from([(
+        root.clone(),
+        metadata::read_view(storage, &root_path)?,
+        0usize,
+    )]);
+    while let Some((note, content, hops)) = queue.pop_front() {
+        for target in links::link_ids(&content) {
+            if bundle.contains_key(&target) || queue.iter().any(|(q, _, _)| *q == target) {
+                continue;
+            }
+            let target_path = locate_note(&dir, &target);
+            if !target_path.is_file() {
+                broken.insert((note.clone(), target));
+            } else if hops < depth && target != note {
+                queue.push_back((
+                    target,
+                    metadata::read_view(storage, &target_path)?,
+                    hops + 1,
+                ));
+            }
+        }
+        bundle.insert(note, content);
+    }
+
+    let dest = prepare_dest(&dest)?;
+    let mut entries = Vec::new();
+    for (note, content) in &bundle {
+        let exported = links::replace_links(content, |target, inner| {
+            let target = sanitize_id(target);
+            if !bundle.contains_key(&target) {
+                return None;
+            }
+            let label = inner
+                .split_once('|')
+                .map(|(_, alias)| alias)
+                .unwrap_or(inner);
+            Some(format!("[{}]({target}.md)", label.trim()))
+        });
+        fs::write(dest.join(format!("{note}.md")), exported)
+            .map_err(|e| format!("Failed to export note ({note}): {e}"))?;
+        entries.push((note.clone(), note_title(note, content)));
+    }
+
+    entries.sort_by(|a, b| a.1.to_lowercase().cmp(&b.1.to_lowercase()));
+    let mut index = format!(
+        "# Notes linked from {}\n\n",
+        note_title(&root, &bundle[&root])
+    );
+    for (note, title) in &entries {
+        index.push_str(&format!("- [{title}]({note}.md)\n"));
+    }
+    if !broken.is_empty() {
+        index.push_str("\n## Broken links\n\n");
+        for (from, target) in &broken {
+            index.push_str(&format!(
+                "- [{from}]({from}.md) links to missing `{target}`\n"
+            ));
+        }
+    }
+    fs::write(dest.join(EXPORT_INDEX_FILE), index)
+        .map_err(|e| format!("Failed to write export index: {e}"))?;
+
+    Ok(bundle.len())
+}
+
+/// Page file for a note's permalink slug, so a site hosted at the publish
+/// base URL serves each note at its permalink. Slugs can't contain dots, so
+/// the fallback for a note slugged `index` can't clash with another page.
+fn page_file(slug: &str) -> String {
+    if format!("{slug}.html") == SITE_INDEX_FILE {
+        format!("{slug}.note.html")
+    } else {
+        format!("{slug}.html")
+    }
+}
+
+fn site_page(title: &str, body: &str) -> String {
+    let title = xml_escape(title);
+    let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n");
+    html.push_str("<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{title}</title>\n"));
+    html.push_str("</head>\n<body>\n");
+    html.push_str(&format!(
+        "<nav><a href=\"{SITE_INDEX_FILE}\">All notes</a></nav>\n"
+    ));
+    html.push_str(&format!("<main>\n<h1>{title}</h1>\n{body}</main>\n"));
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Renders every note marked `publish: true` to an HTML page in `dest`, with
+/// an index page listing them all. Wiki links to other published notes become
+/// relative links; links to anything else render as their plain label, since
+/// the page they'd point at isn't part of the site. Returns the number of
+/// note pages written.
+#[tauri::command]
+pub fn export_site(app: tauri::AppHandle, dest: String) -> Result<usize, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+
+    let notes = permalink::published_notes(&app, &dir)?;
+    let slugs = permalink::slugs(&notes);
+
+    let dest = prepare_dest(&dest)?;
+    let mut entries = Vec::new();
+    for (id, content) in &notes {
+        let linked = links::replace_links(content, |target, inner| {
+            let target = sanitize_id(target);
+            let label = inner
+                .split_once('|')
+                .map(|(_, alias)| alias)
+                .unwrap_or(inner)
+                .trim();
+            Some(match slugs.get(&target) {
+                Some(slug) => format!("[{label}]({})", page_file(slug)),
+                None => label.to_string(),
+            })
+        });
+
+        for attachment in links::attachment_refs(&linked) {
+            // Only plain paths inside the attachments folder.
+            if attachment.split('/').any(|part| part == "..") {
+                continue;
+            }
+            let source = dir.join(&attachment);
+            if !source.is_file() {
+                continue;
+            }
+            let target = dest.join(&attachment);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create attachments dir: {e}"))?;
+            }
+            fs::copy(&source, &target)
+                .map_err(|e| format!("Failed to copy attachment ({attachment}): {e}"))?;
+        }
+
+        let title = note_title(id, content);
+        fs::write(
+            dest.join(page_file(&slugs[id])),
+            site_page(&title, &render::to_html(&linked)),
+        )
+        .map_err(|e| format!("Failed to write page ({id}): {e}"))?;
+        entries.push((id.clone(), title));
+    }
+
+    entries.sort_by(|a, b| a.1.to_lowercase().cmp(&b.1.to_lowercase()));
+    let mut list = String::from("<ul>\n");
+    for (id, title) in &entries {
+        list.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            page_file(&slugs[id]),
+            xml_escape(title)
+        ));
+    }
+    list.push_str("</ul>\n");
+    fs::write(dest.join(SITE_INDEX_FILE), site_page("Notes", &list))
+        .map_err(|e| format!("Failed to write site index: {e}"))?;
+
+    Ok(entries.len())
+}
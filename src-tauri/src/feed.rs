@@ -0,0 +1,99 @@
+use std::{fs, path::PathBuf, time::SystemTime};
+
+use crate::{
+    blocks::{parse_blocks, BlockKind},
+    dates, ensure_notes_dir, file_times_ms, locate_note, note_title, permalink, pin, render,
+    system_time_ms,
+};
+
+const FEED_FILE: &str = "feed.xml";
+const FEED_TITLE: &str = "Notes";
+
+/// Escapes text for XML (and HTML) content and attribute values.
+pub fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Rendered HTML of the note's first paragraph.
+fn summary_html(content: &str) -> String {
+    parse_blocks(content)
+        .into_iter()
+        .find(|block| block.kind == BlockKind::Paragraph)
+        .map(|block| render::to_html(&block.text))
+        .unwrap_or_default()
+}
+
+/// Writes an Atom feed of the `limit` most recently modified notes marked
+/// `publish: true`. Returns the path written.
+#[tauri::command]
+pub fn generate_feed(app: tauri::AppHandle, dest: String, limit: usize) -> Result<String, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+
+    let published = permalink::published_notes(&app, &dir)?;
+    // Slugs are numbered over every published note, not just the ones in the
+    // feed, so entries link where the site export puts them.
+    let slugs = permalink::slugs(&published);
+    let base = permalink::base_url(&app)?;
+
+    let mut notes = Vec::new();
+    for (id, content) in published {
+        let (created, modified) = file_times_ms(&locate_note(&dir, &id))?;
+        notes.push((id, content, created, modified));
+    }
+    notes.sort_by(|a, b| b.3.cmp(&a.3).then_with(|| a.0.cmp(&b.0)));
+    notes.truncate(limit);
+
+    let updated = notes
+        .first()
+        .map(|note| note.3)
+        .unwrap_or_else(|| system_time_ms(SystemTime::now()));
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    xml.push_str(&format!("  <title>{FEED_TITLE}</title>\n"));
+    xml.push_str("  <id>urn:note-manager:feed</id>\n");
+    xml.push_str(&format!(
+        "  <updated>{}</updated>\n",
+        dates::format_rfc3339(updated)
+    ));
+    for (id, content, created, modified) in &notes {
+        xml.push_str("  <entry>\n");
+        xml.push_str(&format!(
+            "    <title>{}</title>\n",
+            xml_escape(&note_title(id, content))
+        ));
+        xml.push_str(&format!("    <id>urn:note-manager:note:{id}</id>\n"));
+        if let Some(base) = &base {
+            xml.push_str(&format!(
+                "    <link href=\"{}\"/>\n",
+                xml_escape(&format!("{base}/{}", slugs[id]))
+            ));
+        }
+        xml.push_str(&format!(
+            "    <published>{}</published>\n",
+            dates::format_rfc3339(*created)
+        ));
+        xml.push_str(&format!(
+            "    <updated>{}</updated>\n",
+            dates::format_rfc3339(*modified)
+        ));
+        xml.push_str(&format!(
+            "    <summary type=\"html\">{}</summary>\n",
+            xml_escape(&summary_html(content))
+        ));
+        xml.push_str("  </entry>\n");
+    }
+    xml.push_str("</feed>\n");
+
+    let mut dest = PathBuf::from(dest);
+    if dest.is_dir() {
+        dest = dest.join(FEED_FILE);
+    } else if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create feed dir: {e}"))?;
+    }
+    fs::write(&dest, xml).map_err(|e| format!("Failed to write feed: {e}"))?;
+    Ok(dest.to_string_lossy().to_string())
+}
@@ -0,0 +1,230 @@
+// Minimal reader/writer for the YAML-ish frontmatter block at the top of a note:
+//
+//     ---
+//     title: Groceries
+//     tags: [home, weekly]
+//     ---
+//
+// Only flat `key: value` pairs and simple lists are understood. Edits rewrite the
+// affected line(s) in place so everything else in the block survives untouched.
+
+/// Splits `content` into the text between the `---` fences (if the note starts
+/// with a frontmatter block) and the body after the closing fence.
+pub fn split(content: &str) -> (Option<&str>, &str) {
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (None, content);
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    (None, content)
+}
+
+/// `content` with its frontmatter block (the text between the fences, as
+/// from [`split`]) replaced by `fm`, or removed with `None`. The body is kept
+/// byte for byte.
+pub fn replace_block(content: &str, fm: Option<&str>) -> String {
+    let body = body(content);
+    let Some(fm) = fm else {
+        return body.to_string();
+    };
+    let nl = if fm.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut out = format!("---{nl}{fm}");
+    if !fm.is_empty() && !fm.ends_with('\n') {
+        out.push_str(nl);
+    }
+    out.push_str("---");
+    out.push_str(nl);
+    out.push_str(body);
+    out
+}
+
+/// The note body with any frontmatter block removed.
+pub fn body(content: &str) -> &str {
+    split(content).1
+}
+
+fn key_of(line: &str) -> Option<&str> {
+    if line.starts_with([' ', '\t', '-', '#']) {
+        return None;
+    }
+    let (key, _) = line.split_once(':')?;
+    let key = key.trim();
+    (!key.is_empty()).then_some(key)
+}
+
+fn unquote(raw: &str) -> String {
+    let raw = raw.trim();
+    if raw.len() >= 2 && raw.starts_with('"') && raw.ends_with('"') {
+        return raw[1..raw.len() - 1]
+            .replace("\\\"", "\"")
+            .replace("\\\\", "\\");
+    }
+    if raw.len() >= 2 && raw.starts_with('\'') && raw.ends_with('\'') {
+        return raw[1..raw.len() - 1].replace("''", "'");
+    }
+    raw.to_string()
+}
+
+/// Formats `value` as a YAML scalar, quoting it only when a bare value would be
+/// read back differently.
+pub fn scalar(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value != value.trim()
+        || value.contains(": ")
+        || value.contains(" #")
+        || value.ends_with(':')
+        || value.starts_with([
+            '"', '\'', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '%', '@', '`', '-',
+        ]);
+    if needs_quotes {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Formats `items` as an inline YAML list, `[a, b]`.
+pub fn inline_list(items: &[String]) -> String {
+    let items: Vec<String> = items.iter().map(|item| scalar(item)).collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// The value of a top-level `key: value` field, unquoted.
+pub fn field(content: &str, key: &str) -> Option<String> {
+    let (fm, _) = split(content);
+    fm?.lines()
+        .find(|line| key_of(line) == Some(key))
+        .and_then(|line| line.split_once(':'))
+        .map(|(_, value)| unquote(value))
+        .filter(|value| !value.is_empty())
+}
+
+/// The items of a list field, written either inline (`tags: [a, b]` or
+/// `tags: a, b`) or as a block of `- item` lines.
+pub fn list(content: &str, key: &str) -> Vec<String> {
+    let (Some(fm), _) = split(content) else {
+        return Vec::new();
+    };
+
+    let mut lines = fm.lines();
+    let Some(line) = lines.by_ref().find(|line| key_of(line) == Some(key)) else {
+        return Vec::new();
+    };
+    let inline = line.split_once(':').map(|(_, v)| v.trim()).unwrap_or("");
+
+    let items: Vec<String> = if inline.is_empty() {
+        lines
+            .take_while(|line| key_of(line).is_none())
+            .filter_map(|line| line.trim_start().strip_prefix('-'))
+            .map(unquote)
+            .collect()
+    } else {
+        let inline = inline
+            .strip_prefix('[')
+            .and_then(|v| v.strip_suffix(']'))
+            .unwrap_or(inline);
+        inline.split(',').map(unquote).collect()
+    };
+
+    items.into_iter().filter(|item| !item.is_empty()).collect()
+}
+
+/// Every top-level field of a frontmatter block as `(key, raw value)`, in
+/// order. A block list's value keeps its item lines, starting with a line
+/// break: `"\n  - a\n  - b"`.
+pub fn raw_entries(fm: &str) -> Vec<(String, String)> {
+    let mut entries: Vec<(String, String)> = Vec::new();
+    for line in fm.lines() {
+        if let Some(key) = key_of(line) {
+            let value = line.split_once(':').map(|(_, v)| v.trim()).unwrap_or("");
+            entries.push((key.to_string(), value.to_string()));
+        } else if let Some((_, value)) = entries.last_mut() {
+            if !line.trim().is_empty() {
+                value.push('\n');
+                value.push_str(line);
+            }
+        }
+    }
+    entries
+}
+
+// A multi-line value (block list) continues on the next line, in the file's
+// line-ending style.
+fn field_line(key: &str, value: &str, nl: &str) -> String {
+    let value = value.replace('\n', nl);
+    if value.starts_with(nl) {
+        format!("{key}:{value}")
+    } else {
+        format!("{key}: {value}")
+    }
+}
+
+/// Returns `content` with `key` set to the already-formatted `value`, or with
+/// the field removed when `value` is `None`. A value spanning several lines
+/// (a block list) is replaced as a whole. Adding a field to a note without
+/// frontmatter creates the block.
+pub fn set_raw(content: &str, key: &str, value: Option<&str>) -> String {
+    let (fm, body) = split(content);
+    let Some(fm) = fm else {
+        return match value {
+            Some(value) => format!("---\n{}\n---\n{content}", field_line(key, value, "\n")),
+            None => content.to_string(),
+        };
+    };
+
+    let nl = if fm.contains("\r\n") || content.starts_with("---\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut replaced = false;
+    let mut skipping = false;
+    for line in fm.lines() {
+        if skipping {
+            if key_of(line).is_none() && !line.trim().is_empty() {
+                continue;
+            }
+            skipping = false;
+        }
+        if key_of(line) == Some(key) && !replaced {
+            replaced = true;
+            skipping = true;
+            if let Some(value) = value {
+                lines.push(field_line(key, value, nl));
+            }
+            continue;
+        }
+        lines.push(line.to_string());
+    }
+    if !replaced {
+        if let Some(value) = value {
+            lines.push(field_line(key, value, nl));
+        }
+    }
+
+    let mut out = format!("---{nl}");
+    for line in lines {
+        out.push_str(&line);
+        out.push_str(nl);
+    }
+    out.push_str("---");
+    out.push_str(nl);
+    out.push_str(body);
+    out
+}
+
+/// [`set_raw`] for a plain string value, quoting it when necessary.
+pub fn set_field(content: &str, key: &str, value: Option<&str>) -> String {
+    set_raw(content, key, value.map(scalar).as_deref())
+}
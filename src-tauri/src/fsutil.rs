@@ -0,0 +1,118 @@
+use std::{fs, io::Write, path::Path};
+
+/// Recursively copies the contents of `src` into `dst`, creating `dst`.
+/// Returns the number of files copied.
+pub fn copy_dir(src: &Path, dst: &Path) -> Result<usize, String> {
+    copy_dir_excluding(src, dst, &[])
+}
+
+/// [`copy_dir`], skipping the entries of `src` (not of its subfolders) named
+/// in `skip`.
+pub fn copy_dir_excluding(src: &Path, dst: &Path, skip: &[&str]) -> Result<usize, String> {
+    fs::create_dir_all(dst).map_err(|e| format!("Failed to create {}: {e}", dst.display()))?;
+
+    let mut copied = 0;
+    let entries =
+        fs::read_dir(src).map_err(|e| format!("Failed to read {}: {e}", src.display()))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read {}: {e}", src.display()))?;
+        if skip.iter().any(|name| entry.file_name() == *name) {
+            continue;
+        }
+        let from = entry.path();
+        let to = dst.join(entry.file_name());
+        if from.is_dir() {
+            copied += copy_dir(&from, &to)?;
+        } else {
+            fs::copy(&from, &to).map_err(|e| format!("Failed to copy {}: {e}", from.display()))?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+/// Checks that every file under `src` exists under `dst` with identical bytes.
+pub fn verify_copy(src: &Path, dst: &Path) -> Result<(), String> {
+    let entries =
+        fs::read_dir(src).map_err(|e| format!("Failed to read {}: {e}", src.display()))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read {}: {e}", src.display()))?;
+        let from = entry.path();
+        let to = dst.join(entry.file_name());
+        if from.is_dir() {
+            verify_copy(&from, &to)?;
+            continue;
+        }
+
+        let original =
+            fs::read(&from).map_err(|e| format!("Failed to read {}: {e}", from.display()))?;
+        let copy = fs::read(&to).map_err(|e| format!("Failed to read {}: {e}", to.display()))?;
+        if original != copy {
+            return Err(format!("Copy of {} does not match", from.display()));
+        }
+    }
+    Ok(())
+}
+
+/// Fails with "Insufficient disk space" unless the volume holding `dir` has
+/// room for `bytes` more plus `margin`. When free space can't be determined
+/// the write is allowed; it would fail on its own if the disk were full.
+pub fn ensure_space(dir: &Path, bytes: u64, margin: u64) -> Result<(), String> {
+    match fs2::available_space(dir) {
+        Ok(available) if available < bytes.saturating_add(margin) => {
+            Err("Insufficient disk space".to_string())
+        }
+        Ok(_) => Ok(()),
+        Err(e) => {
+            eprintln!("Failed to check free space for {}: {e}", dir.display());
+            Ok(())
+        }
+    }
+}
+
+/// Overwrites the file with zeros, flushes it, then removes it. Best effort:
+/// filesystems that copy on write or journal data may keep the old blocks.
+pub fn shred_file(path: &Path) -> Result<(), String> {
+    let len = fs::metadata(path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?
+        .len();
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    let zeros = [0u8; 64 * 1024];
+    let mut left = len;
+    while left > 0 {
+        let chunk = left.min(zeros.len() as u64) as usize;
+        file.write_all(&zeros[..chunk])
+            .map_err(|e| format!("Failed to overwrite {}: {e}", path.display()))?;
+        left -= chunk as u64;
+    }
+    file.sync_all()
+        .map_err(|e| format!("Failed to overwrite {}: {e}", path.display()))?;
+    drop(file);
+    fs::remove_file(path).map_err(|e| format!("Failed to remove {}: {e}", path.display()))
+}
+
+/// [`shred_file`] for every file under `dir`, then removes `dir` itself.
+pub fn shred_dir(dir: &Path) -> Result<(), String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {e}", dir.display()))?;
+    for entry in entries {
+        let path = entry
+            .map_err(|e| format!("Failed to read {}: {e}", dir.display()))?
+            .path();
+        if path.is_dir() {
+            shred_dir(&path)?;
+        } else {
+            shred_file(&path)?;
+        }
+    }
+    fs::remove_dir(dir).map_err(|e| format!("Failed to remove {}: {e}", dir.display()))
+}
+
+pub fn is_empty_dir(path: &Path) -> bool {
+    fs::read_dir(path)
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(false)
+}
@@ -0,0 +1,270 @@
+use git2::{Repository, Signature, Sort, Status, StatusOptions};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cache, ensure_notes_dir, history, is_md_file, links, locate_note, sanitize_id, settings,
+};
+
+/// Note file names (relative to the notes dir) by git state. A file can be in
+/// more than one list, e.g. staged and then modified again.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GitStatus {
+    modified: Vec<String>,
+    staged: Vec<String>,
+    untracked: Vec<String>,
+    deleted: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitCommit {
+    /// Full hex object id of the commit.
+    id: String,
+    message: String,
+    author: String,
+    time_ms: i64,
+}
+
+fn open_repo(dir: &Path) -> Result<Repository, String> {
+    Repository::discover(dir).map_err(|_| "Not a git repository".to_string())
+}
+
+fn workdir(repo: &Repository) -> Result<PathBuf, String> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "Repository has no working directory".to_string())?;
+    fs::canonicalize(workdir).map_err(|e| format!("Failed to resolve repository dir: {e}"))
+}
+
+/// `path` relative to the repository's working tree, as git stores it.
+fn repo_relative(repo: &Repository, path: &Path) -> Result<PathBuf, String> {
+    let workdir = workdir(repo)?;
+
+    // The file itself may already be gone (delete), so resolve its folder.
+    let parent = path.parent().unwrap_or(path);
+    let parent =
+        fs::canonicalize(parent).map_err(|e| format!("Failed to resolve note dir: {e}"))?;
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("Invalid note path: {}", path.display()))?;
+
+    parent
+        .join(file_name)
+        .strip_prefix(&workdir)
+        .map(Path::to_path_buf)
+        .map_err(|_| "Note is outside the git repository".to_string())
+}
+
+fn commit_path(path: &Path, message: &str) -> Result<(), String> {
+    let dir = path.parent().unwrap_or(path);
+    let repo = open_repo(dir)?;
+    let rel = repo_relative(&repo, path)?;
+
+    let mut index = repo
+        .index()
+        .map_err(|e| format!("Failed to open git index: {e}"))?;
+    let staged = if path.exists() {
+        index.add_path(&rel)
+    } else {
+        index.remove_path(&rel)
+    };
+    staged.map_err(|e| format!("Failed to stage {}: {e}", rel.display()))?;
+    index
+        .write()
+        .map_err(|e| format!("Failed to write git index: {e}"))?;
+
+    let tree_id = index
+        .write_tree()
+        .map_err(|e| format!("Failed to write git tree: {e}"))?;
+    let tree = repo
+        .find_tree(tree_id)
+        .map_err(|e| format!("Failed to read git tree: {e}"))?;
+
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    if parent.as_ref().map(|p| p.tree_id()) == Some(tree_id) {
+        // Nothing changed for git (e.g. saving identical content).
+        return Ok(());
+    }
+
+    let signature = repo
+        .signature()
+        .or_else(|_| Signature::now("note-manager", "note-manager@localhost"))
+        .map_err(|e| format!("Failed to build commit signature: {e}"))?;
+    let parents: Vec<_> = parent.iter().collect();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )
+    .map_err(|e| format!("Failed to commit: {e}"))?;
+    Ok(())
+}
+
+/// Commits the change to `path` if auto-commit is enabled. Never fails the
+/// caller: the note operation already succeeded, so problems are only logged.
+pub fn autocommit(app: &tauri::AppHandle, path: &Path, message: &str) {
+    match settings::load(app) {
+        Ok(settings) if settings.git_autocommit => {
+            if let Err(e) = commit_path(path, message) {
+                eprintln!("git auto-commit skipped: {e}");
+            }
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("git auto-commit skipped: {e}"),
+    }
+}
+
+#[tauri::command]
+pub fn git_log(app: tauri::AppHandle, id: String) -> Result<Vec<GitCommit>, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let path = locate_note(&dir, &sanitize_id(&id));
+    let repo = open_repo(&dir)?;
+    let rel = repo_relative(&repo, &path)?;
+
+    let mut walk = repo
+        .revwalk()
+        .map_err(|e| format!("Failed to walk git history: {e}"))?;
+    if walk.push_head().is_err() {
+        // Repository without any commits yet.
+        return Ok(Vec::new());
+    }
+    walk.set_sorting(Sort::TIME)
+        .map_err(|e| format!("Failed to walk git history: {e}"))?;
+
+    let blob_at = |commit: &git2::Commit| {
+        commit
+            .tree()
+            .ok()
+            .and_then(|tree| tree.get_path(&rel).ok())
+            .map(|entry| entry.id())
+    };
+
+    let mut commits = Vec::new();
+    for oid in walk {
+        let oid = oid.map_err(|e| format!("Failed to walk git history: {e}"))?;
+        let commit = repo
+            .find_commit(oid)
+            .map_err(|e| format!("Failed to read commit {oid}: {e}"))?;
+
+        // Only commits where this file was added, changed or removed.
+        let current = blob_at(&commit);
+        let previous = commit.parent(0).ok().and_then(|p| blob_at(&p));
+        if current == previous {
+            continue;
+        }
+
+        commits.push(GitCommit {
+            id: oid.to_string(),
+            message: commit.message().unwrap_or_default().trim().to_string(),
+            author: commit.author().name().unwrap_or_default().to_string(),
+            time_ms: commit.time().seconds() * 1000,
+        });
+    }
+    Ok(commits)
+}
+
+#[tauri::command]
+pub fn git_status(app: tauri::AppHandle) -> Result<GitStatus, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let repo = open_repo(&dir)?;
+    let notes_rel = fs::canonicalize(&dir)
+        .map_err(|e| format!("Failed to resolve notes dir: {e}"))?
+        .strip_prefix(workdir(&repo)?)
+        .map(Path::to_path_buf)
+        .map_err(|_| "Notes dir is outside the git repository".to_string())?;
+
+    let mut options = StatusOptions::new();
+    options.include_untracked(true).recurse_untracked_dirs(true);
+    let statuses = repo
+        .statuses(Some(&mut options))
+        .map_err(|e| format!("Failed to read git status: {e}"))?;
+
+    let mut status = GitStatus::default();
+    for entry in statuses.iter() {
+        let Some(path) = entry.path().map(Path::new) else {
+            continue;
+        };
+        // Only note files themselves, not history, trash or other folders.
+        if path.parent() != Some(notes_rel.as_path()) || !is_md_file(path) {
+            continue;
+        }
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+
+        let flags = entry.status();
+        let lists = [
+            (
+                &mut status.staged,
+                Status::INDEX_NEW
+                    | Status::INDEX_MODIFIED
+                    | Status::INDEX_DELETED
+                    | Status::INDEX_RENAMED
+                    | Status::INDEX_TYPECHANGE,
+            ),
+            (
+                &mut status.modified,
+                Status::WT_MODIFIED | Status::WT_RENAMED | Status::WT_TYPECHANGE,
+            ),
+            (&mut status.untracked, Status::WT_NEW),
+            (
+                &mut status.deleted,
+                Status::WT_DELETED | Status::INDEX_DELETED,
+            ),
+        ];
+        for (list, mask) in lists {
+            if flags.intersects(mask) {
+                list.push(name.clone());
+            }
+        }
+    }
+
+    for list in [
+        &mut status.modified,
+        &mut status.staged,
+        &mut status.untracked,
+        &mut status.deleted,
+    ] {
+        list.sort();
+    }
+    Ok(status)
+}
+
+/// Restores the note's file from `HEAD`, discarding uncommitted changes to
+/// that one file only. The discarded content is kept in the note's history.
+#[tauri::command]
+pub fn git_revert_note(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    let dir = ensure_notes_dir(&app)?;
+    let id = sanitize_id(&id);
+    let path = locate_note(&dir, &id);
+    let repo = open_repo(&dir)?;
+    let rel = repo_relative(&repo, &path)?;
+
+    let head = repo
+        .head()
+        .and_then(|head| head.peel_to_tree())
+        .map_err(|_| "Repository has no commits yet".to_string())?;
+    if head.get_path(&rel).is_err() {
+        return Err(format!("Note is not tracked in git: {id}"));
+    }
+
+    if let Ok(current) = fs::read(&path) {
+        history::record(&app, &dir, &id, &current)?;
+    }
+    let mut checkout = git2::build::CheckoutBuilder::new();
+    checkout.force().disable_pathspec_match(true).path(&rel);
+    repo.checkout_head(Some(&mut checkout))
+        .map_err(|e| format!("Failed to restore {}: {e}", rel.display()))?;
+
+    cache::invalidate(&app, &id);
+    links::invalidate(&app);
+    Ok(())
+}
@@ -0,0 +1,17 @@
+// Stable, dependency-free hashing (64-bit FNV-1a). Not cryptographic: it is
+// used for short ids and change detection, where it only has to be fast and
+// identical across platforms and releases (unlike `DefaultHasher`).
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+pub fn fnv1a64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// [`fnv1a64`] as 16 lowercase hex digits.
+pub fn hex_hash(bytes: &[u8]) -> String {
+    format!("{:016x}", fnv1a64(bytes))
+}
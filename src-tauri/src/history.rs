@@ -0,0 +1,430 @@
+// Previous versions of each note, kept in `.history/<id>/` and named after
+// when that version was replaced: `<ms>.md` holds a full copy, `<ms>.diff`
+// (with `history_diffs` on, or after `compress_history`) the line edits
+// turning the version before it into this one.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    cache, dates,
+    diff::{diff_slices, DiffKind},
+    ensure_notes_dir, existing_note_path, file_times_ms, git, library_notes, links, pin,
+    sanitize_id, settings, system_time_ms, HISTORY_DIR,
+};
+
+const FULL_EXT: &str = "md";
+const DIFF_EXT: &str = "diff";
+
+// Every Nth version stays a full copy, so reading one never replays more
+// than N - 1 diffs. Version 0 (the oldest) is always full.
+const FULL_SNAPSHOT_EVERY: usize = 25;
+
+/// One step of a stored diff, applied to the previous version's lines.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Hunk {
+    Keep(usize),
+    Drop(usize),
+    Add(String),
+}
+
+pub fn history_dir(dir: &Path, id: &str) -> PathBuf {
+    dir.join(HISTORY_DIR).join(id)
+}
+
+fn is_diff(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == DIFF_EXT)
+}
+
+// Line endings stay part of each line, so applying a diff is byte-exact.
+fn split_lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+fn make_diff(old: &str, new: &str) -> Vec<Hunk> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    for (kind, line) in diff_slices(&split_lines(old), &split_lines(new)) {
+        match (kind, hunks.last_mut()) {
+            (DiffKind::Same, Some(Hunk::Keep(n))) | (DiffKind::Removed, Some(Hunk::Drop(n))) => {
+                *n += 1
+            }
+            (DiffKind::Added, Some(Hunk::Add(text))) => text.push_str(line),
+            (DiffKind::Same, _) => hunks.push(Hunk::Keep(1)),
+            (DiffKind::Removed, _) => hunks.push(Hunk::Drop(1)),
+            (DiffKind::Added, _) => hunks.push(Hunk::Add(line.to_string())),
+        }
+    }
+    hunks
+}
+
+fn apply_diff(base: &str, hunks: &[Hunk]) -> Result<String, String> {
+    let lines = split_lines(base);
+    let mut out = String::with_capacity(base.len());
+    let mut at = 0;
+    for hunk in hunks {
+        match hunk {
+            Hunk::Keep(n) | Hunk::Drop(n) if at + n > lines.len() => {
+                return Err("Corrupt history diff".to_string());
+            }
+            Hunk::Keep(n) => {
+                lines[at..at + n].iter().for_each(|line| out.push_str(line));
+                at += n;
+            }
+            Hunk::Drop(n) => at += n,
+            Hunk::Add(text) => out.push_str(text),
+        }
+    }
+    if at != lines.len() {
+        return Err("Corrupt history diff".to_string());
+    }
+    Ok(out)
+}
+
+fn read_text(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("Failed to read history version: {e}"))
+}
+
+/// Content of the version stored at `path`, given the content of the one
+/// before it (needed when this one is a diff).
+fn read_version(path: &Path, before: Option<&[u8]>) -> Result<Vec<u8>, String> {
+    if !is_diff(path) {
+        return fs::read(path).map_err(|e| format!("Failed to read history version: {e}"));
+    }
+    let before = before
+        .and_then(|b| std::str::from_utf8(b).ok())
+        .ok_or_else(|| "History diff has no text version to apply to".to_string())?;
+    let hunks: Vec<Hunk> = serde_json::from_str(&read_text(path)?)
+        .map_err(|e| format!("Failed to parse history diff: {e}"))?;
+    Ok(apply_diff(before, &hunks)?.into_bytes())
+}
+
+/// Content of stored version `index` in `versions` (as from [`versions`]),
+/// replaying diffs from the nearest full copy before it.
+fn content_at(versions: &[(u64, PathBuf)], index: usize) -> Result<Vec<u8>, String> {
+    let base = (0..=index)
+        .rev()
+        .find(|&i| !is_diff(&versions[i].1))
+        .ok_or_else(|| "History has no full version to start from".to_string())?;
+
+    let mut content = read_version(&versions[base].1, None)?;
+    for (_, path) in &versions[base + 1..=index] {
+        content = read_version(path, Some(&content))?;
+    }
+    Ok(content)
+}
+
+/// Writes version `index` of a history whose previous version is `before`:
+/// as a diff when that's allowed and smaller, otherwise as a full copy.
+fn write_version(
+    folder: &Path,
+    ms: u64,
+    index: usize,
+    before: Option<&[u8]>,
+    content: &[u8],
+) -> Result<(), String> {
+    let diff = match (before, std::str::from_utf8(content)) {
+        (Some(before), Ok(text)) if index % FULL_SNAPSHOT_EVERY != 0 => std::str::from_utf8(before)
+            .ok()
+            .map(|old| serde_json::to_string(&make_diff(old, text)))
+            .transpose()
+            .map_err(|e| format!("Failed to serialize history diff: {e}"))?
+            .filter(|diff| diff.len() < content.len()),
+        _ => None,
+    };
+
+    let (ext, bytes) = match &diff {
+        Some(diff) => (DIFF_EXT, diff.as_bytes()),
+        None => (FULL_EXT, content),
+    };
+    fs::write(folder.join(format!("{ms}.{ext}")), bytes)
+        .map_err(|e| format!("Failed to write history version: {e}"))
+}
+
+/// Stores `previous` as a version of `id` replaced now, as a diff against
+/// the version before it when `history_diffs` is on.
+pub fn record(app: &tauri::AppHandle, dir: &Path, id: &str, previous: &[u8]) -> Result<(), String> {
+    let folder = history_dir(dir, id);
+    fs::create_dir_all(&folder).map_err(|e| format!("Failed to create history dir: {e}"))?;
+    let existing = versions(dir, id)?;
+
+    // Two saves within the same millisecond get consecutive names.
+    let mut ms = system_time_ms(SystemTime::now());
+    while existing.iter().any(|(other, _)| *other == ms) {
+        ms += 1;
+    }
+
+    let before = match existing.len() {
+        0 => None,
+        _ if !settings::load(app)?.history_diffs => None,
+        n => Some(content_at(&existing, n - 1)?),
+    };
+    write_version(&folder, ms, existing.len(), before.as_deref(), previous)
+}
+
+/// Stored versions of `id` as `(replaced_ms, path)`, oldest first.
+pub fn versions(dir: &Path, id: &str) -> Result<Vec<(u64, PathBuf)>, String> {
+    let folder = history_dir(dir, id);
+    if !folder.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut versions = Vec::new();
+    let entries = fs::read_dir(&folder).map_err(|e| format!("Failed to read history: {e}"))?;
+    for entry in entries {
+        let path = entry
+            .map_err(|e| format!("Failed to read history: {e}"))?
+            .path();
+        let known = path
+            .extension()
+            .is_some_and(|ext| ext == FULL_EXT || ext == DIFF_EXT);
+        let ms = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.parse::<u64>().ok());
+        if let Some(ms) = ms.filter(|_| known && path.is_file()) {
+            versions.push((ms, path));
+        }
+    }
+    versions.sort_by_key(|(ms, _)| *ms);
+    Ok(versions)
+}
+
+/// What the note contained just before `ms`, when history has it: the first
+/// version replaced at or after `ms`, as `(replaced_ms, content)`. `None`
+/// when no version was replaced since, i.e. the live file may still hold
+/// that content.
+pub fn version_before(dir: &Path, id: &str, ms: u64) -> Result<Option<(u64, Vec<u8>)>, String> {
+    let stored = versions(dir, id)?;
+    match stored.iter().position(|(replaced, _)| *replaced >= ms) {
+        Some(index) => Ok(Some((stored[index].0, content_at(&stored, index)?))),
+        None => Ok(None),
+    }
+}
+
+/// Replaces the note with its version replaced at `version_ms`. The current
+/// content becomes a version of its own, so a restore can be undone.
+#[tauri::command]
+pub fn restore_version(app: tauri::AppHandle, id: String, version_ms: u64) -> Result<(), String> {
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    let id = sanitize_id(&id);
+
+    let stored = versions(&dir, &id)?;
+    let index = stored
+        .iter()
+        .position(|(ms, _)| *ms == version_ms)
+        .ok_or_else(|| format!("Version not found: {version_ms}"))?;
+    let content = content_at(&stored, index)?;
+
+    let current = fs::read(&path).map_err(|e| format!("Failed to read note file: {e}"))?;
+    if current == content {
+        return Ok(());
+    }
+    record(&app, &dir, &id, &current)?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write note file: {e}"))?;
+    cache::invalidate(&app, &id);
+    links::invalidate(&app);
+    git::autocommit(&app, &path, &format!("Restore {id}"));
+    Ok(())
+}
+
+fn push_timeline_entry(out: &mut String, heading: &str, content: &[u8]) {
+    out.push_str(&format!("## {heading}\n\n"));
+    out.push_str(String::from_utf8_lossy(content).trim_end());
+    out.push_str("\n\n");
+}
+
+/// Writes every stored version of the note, oldest first and then the live
+/// one, to a single markdown file at `dest` (a file, or a folder to put
+/// `<id>-history.md` in). Each version is headed with the time it was
+/// replaced. Returns the path written.
+#[tauri::command]
+pub fn export_history_timeline(
+    app: tauri::AppHandle,
+    id: String,
+    dest: String,
+) -> Result<String, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    let id = sanitize_id(&id);
+
+    let mut out = format!("# History of {id}\n\n");
+    let mut before: Option<Vec<u8>> = None;
+    for (ms, version) in versions(&dir, &id)? {
+        let content = read_version(&version, before.as_deref())?;
+        let heading = format!("Replaced {}", dates::format_rfc3339(ms));
+        push_timeline_entry(&mut out, &heading, &content);
+        before = Some(content);
+    }
+    let current = fs::read(&path).map_err(|e| format!("Failed to read note file: {e}"))?;
+    let (_, modified) = file_times_ms(&path)?;
+    let heading = format!("Current (modified {})", dates::format_rfc3339(modified));
+    push_timeline_entry(&mut out, &heading, &current);
+
+    let mut dest = PathBuf::from(dest);
+    if dest.is_dir() {
+        dest = dest.join(format!("{id}-history.md"));
+    } else if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create export dir: {e}"))?;
+    }
+    fs::write(&dest, out.trim_end().to_string() + "\n")
+        .map_err(|e| format!("Failed to write history timeline: {e}"))?;
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Rewrites full-copy versions as diffs against the version before them
+/// where that's smaller, keeping the oldest (and every 25th) as a full copy.
+/// Returns the bytes saved.
+#[tauri::command]
+pub fn compress_history(app: tauri::AppHandle) -> Result<u64, String> {
+    let dir = ensure_notes_dir(&app)?;
+
+    let mut saved = 0u64;
+    for (id, _) in library_notes(&app, &dir)? {
+        saved += compress_versions(&history_dir(&dir, &id), &versions(&dir, &id)?)?;
+    }
+    Ok(saved)
+}
+
+/// [`compress_history`] for the versions `stored` in one note's history
+/// `folder`.
+fn compress_versions(folder: &Path, stored: &[(u64, PathBuf)]) -> Result<u64, String> {
+    let mut saved = 0u64;
+    // Replayed from the content already read, so rewriting a version's file
+    // doesn't affect reading the next one.
+    let mut before: Option<Vec<u8>> = None;
+    for (index, (ms, path)) in stored.iter().enumerate() {
+        let content = read_version(path, before.as_deref())?;
+        if !is_diff(path) && before.is_some() && index % FULL_SNAPSHOT_EVERY != 0 {
+            write_version(folder, *ms, index, before.as_deref(), &content)?;
+            let diff_path = path.with_extension(DIFF_EXT);
+            if diff_path.is_file() {
+                let diff_len = fs::metadata(&diff_path).map(|m| m.len()).unwrap_or(0);
+                fs::remove_file(path)
+                    .map_err(|e| format!("Failed to remove history version: {e}"))?;
+                saved += (content.len() as u64).saturating_sub(diff_len);
+            }
+        }
+        before = Some(content);
+    }
+    Ok(saved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_library(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("history-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Version `n` of a note long enough that a one-line edit stores as a
+    /// diff: line `n % 40` changes each time, and one line is added.
+    fn version(n: usize) -> String {
+        let mut text = String::new();
+        for line in 0..40 + n {
+            if line == n % 40 {
+                text.push_str(&format!("edited in version {n}\n"));
+            } else {
+                text.push_str(&format!("line {line} of a note that keeps growing\n"));
+            }
+        }
+        text
+    }
+
+    #[test]
+    fn diff_then_apply_gives_the_new_text() {
+        let cases = [
+            ("", "new\n"),
+            ("old\n", ""),
+            ("a\nb\nc\n", "a\nc\nd\n"),
+            ("same\r\nline\r\n", "same\r\nchanged\r\nline\r\n"),
+            ("no newline", "no newline\nat the end"),
+            ("x\ny\n", "x\ny"),
+        ];
+        for (old, new) in cases {
+            assert_eq!(apply_diff(old, &make_diff(old, new)).unwrap(), new);
+        }
+    }
+
+    #[test]
+    fn corrupt_diff_is_an_error() {
+        assert!(apply_diff("one\n", &[Hunk::Keep(2)]).is_err());
+        assert!(apply_diff("one\ntwo\n", &[Hunk::Keep(1)]).is_err());
+    }
+
+    #[test]
+    fn diff_chain_reads_back_across_full_snapshots() {
+        let dir = temp_library("chain");
+        let folder = history_dir(&dir, "note");
+        fs::create_dir_all(&folder).unwrap();
+
+        let count = FULL_SNAPSHOT_EVERY * 2 + 3;
+        for n in 0..count {
+            let before = n.checked_sub(1).map(version);
+            let before = before.as_deref().map(str::as_bytes);
+            write_version(&folder, n as u64 + 1, n, before, version(n).as_bytes()).unwrap();
+        }
+
+        let stored = versions(&dir, "note").unwrap();
+        assert_eq!(stored.len(), count);
+        for (n, (_, path)) in stored.iter().enumerate() {
+            assert_eq!(is_diff(path), n % FULL_SNAPSHOT_EVERY != 0, "version {n}");
+        }
+        for n in 0..count {
+            assert_eq!(content_at(&stored, n).unwrap(), version(n).into_bytes());
+        }
+        assert_eq!(
+            version_before(&dir, "note", FULL_SNAPSHOT_EVERY as u64 + 2).unwrap(),
+            Some((
+                FULL_SNAPSHOT_EVERY as u64 + 2,
+                version(FULL_SNAPSHOT_EVERY + 1).into_bytes()
+            ))
+        );
+        assert_eq!(
+            version_before(&dir, "note", count as u64 + 1).unwrap(),
+            None
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn compress_keeps_every_version_byte_for_byte() {
+        let dir = temp_library("compress");
+        let folder = history_dir(&dir, "note");
+        fs::create_dir_all(&folder).unwrap();
+
+        let count = FULL_SNAPSHOT_EVERY + 5;
+        for n in 0..count {
+            write_version(&folder, n as u64 + 1, n, None, version(n).as_bytes()).unwrap();
+        }
+        // A version that isn't UTF-8 has to stay a full copy.
+        let binary = [0xffu8, 0xfe, b'\n'];
+        fs::write(folder.join(format!("{}.md", count + 1)), binary).unwrap();
+
+        let stored = versions(&dir, "note").unwrap();
+        assert!(stored.iter().all(|(_, path)| !is_diff(path)));
+        assert!(compress_versions(&folder, &stored).unwrap() > 0);
+
+        let stored = versions(&dir, "note").unwrap();
+        assert_eq!(stored.len(), count + 1);
+        assert_eq!(
+            stored.iter().filter(|(_, path)| is_diff(path)).count(),
+            count - 2
+        );
+        for n in 0..count {
+            assert_eq!(content_at(&stored, n).unwrap(), version(n).into_bytes());
+        }
+        assert_eq!(content_at(&stored, count).unwrap(), binary);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
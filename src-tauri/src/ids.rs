@@ -0,0 +1,317 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cache, ensure_notes_dir, existing_note_path, frontmatter, hash, history, library_notes, links,
+    locate_note, metadata, note_path, note_title, read_note_content, sanitize_id, scroll, tags,
+    transaction::Transaction, views, ARCHIVE_DIR, ATTACHMENTS_DIR, HISTORY_DIR, TRASH_DIR,
+};
+
+const SHORT_ID_LEN: usize = 8;
+
+/// How `create_note` names new notes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IdStrategy {
+    /// `note_<ms since epoch>`.
+    #[default]
+    Timestamp,
+    /// Random v4 UUID.
+    Uuid,
+    /// Slug of the title given on creation (`untitled` without one).
+    Slug,
+    /// Zero-padded counter stored in the library's `meta.json`.
+    Sequential,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IdIssue {
+    /// The id the app uses, i.e. the filename stem.
+    id: String,
+    /// The conflicting `id` frontmatter field.
+    frontmatter_id: String,
+}
+
+// Device names Windows won't open as files, whatever the extension.
+const WINDOWS_RESERVED: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Fails with "Reserved id" for ids naming an internal folder (with or without
+/// its leading dot) or a Windows device. Checked on every platform, since
+/// libraries get synced between them.
+pub fn ensure_not_reserved(id: &str) -> Result<(), String> {
+    let lower = id.to_ascii_lowercase();
+    let internal = [ATTACHMENTS_DIR, HISTORY_DIR, ARCHIVE_DIR, TRASH_DIR]
+        .iter()
+        .any(|dir| dir.trim_start_matches('.') == lower.trim_start_matches('.'));
+    if internal || WINDOWS_RESERVED.contains(&lower.as_str()) {
+        return Err(format!("Reserved id: {id}"));
+    }
+    Ok(())
+}
+
+/// Lowercase ASCII slug of `text`: runs of spaces and punctuation become a
+/// single `-`, non-ASCII letters are dropped. Always a fixed point of
+/// `sanitize_id`.
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if ch.is_ascii_alphanumeric() {
+            slug.push(ch.to_ascii_lowercase());
+        } else if ch.is_alphanumeric() {
+            continue;
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// `base`, or `base-2`, `base-3`, ... whichever isn't in `taken` yet and
+/// isn't a reserved id.
+pub fn unique_slug(base: &str, taken: &HashSet<String>) -> String {
+    let free =
+        |candidate: &str| !taken.contains(candidate) && ensure_not_reserved(candidate).is_ok();
+    if free(base) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|n| format!("{base}-{n}"))
+        .find(|candidate| free(candidate))
+        .unwrap_or_else(|| base.to_string())
+}
+
+/// Stages moving what else is keyed by a note's id or file along with the
+/// renamed notes: history versions, the metadata sidecar, scroll positions,
+/// view times and tag positions. `moves` pairs each note's old and new file.
+/// Leftovers already under a new id are left alone rather than failing the
+/// rename.
+pub(crate) fn stage_note_state(
+    app: &tauri::AppHandle,
+    dir: &Path,
+    txn: &mut Transaction,
+    moves: &[(PathBuf, PathBuf)],
+    renames: &HashMap<String, String>,
+) -> Result<(), String> {
+    for (old, new) in renames {
+        let (from, to) = (
+            history::history_dir(dir, old),
+            history::history_dir(dir, new),
+        );
+        if from.is_dir() && !to.exists() {
+            txn.rename(&from, &to);
+        }
+    }
+    for (from, to) in moves {
+        metadata::stage_move(txn, from, to);
+    }
+    scroll::stage_renames(app, txn, renames)?;
+    views::stage_renames(app, txn, renames)?;
+    tags::stage_renames(dir, txn, renames)
+}
+
+#[tauri::command]
+pub fn slugify_ids(app: tauri::AppHandle, dry_run: bool) -> Result<Vec<(String, String)>, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let storage = metadata::active(&app)?;
+
+    let mut notes = Vec::new();
+    let mut titles = HashMap::new();
+    for (id, path) in library_notes(&app, &dir)? {
+        let content = read_note_content(&path)?;
+        titles.insert(
+            id.clone(),
+            note_title(&id, &metadata::view(storage, &path, content.clone())?),
+        );
+        notes.push((id, path, content));
+    }
+    notes.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // Every current id stays reserved, so a new slug can never land on a file
+    // that is itself about to be renamed.
+    let mut taken: HashSet<String> = notes.iter().map(|(id, _, _)| id.clone()).collect();
+    let mut plan = Vec::new();
+    for (id, _, _) in &notes {
+        let title = &titles[id];
+        if title == id {
+            // No real title to derive a slug from.
+            continue;
+        }
+        let slug = slugify(title);
+        if slug.is_empty() || slug == *id || ensure_not_reserved(&slug).is_err() {
+            continue;
+        }
+        let slug = unique_slug(&slug, &taken);
+        taken.insert(slug.clone());
+        plan.push((id.clone(), slug));
+    }
+
+    if dry_run || plan.is_empty() {
+        return Ok(plan);
+    }
+
+    // Link updates and renames land together or not at all.
+    let renames: HashMap<String, String> = plan.iter().cloned().collect();
+    let mut txn = Transaction::new();
+    for (_, path, content) in &notes {
+        let updated = links::rewrite_links(content, &renames);
+        if updated != *content {
+            txn.write(path, updated);
+        }
+    }
+    let mut moves = Vec::new();
+    for (id, path, _) in &notes {
+        if let Some(new) = renames.get(id) {
+            // Renamed where it is; with the nested layout that's its folder.
+            let target = note_path(path.parent().unwrap_or(&dir), new);
+            txn.rename(path, &target);
+            moves.push((path.clone(), target));
+        }
+    }
+    stage_note_state(&app, &dir, &mut txn, &moves, &renames)?;
+    txn.commit()?;
+
+    cache::clear(&app);
+    links::invalidate(&app);
+    Ok(plan)
+}
+
+/// Renames note `id` to a slug of `title`, numbered if another note has it,
+/// and points every wiki link at the new id. `content` is what the note
+/// should contain afterwards; it's written in the same transaction. Returns
+/// `None` (writing nothing) when the title has no slug or already matches.
+pub fn rename_to_title(
+    app: &tauri::AppHandle,
+    dir: &Path,
+    id: &str,
+    title: &str,
+    content: &str,
+) -> Result<Option<String>, String> {
+    let slug = slugify(title);
+    if slug.is_empty() || slug == id {
+        return Ok(None);
+    }
+
+    let notes = library_notes(app, dir)?;
+    let taken: HashSet<String> = notes
+        .iter()
+        .map(|(other, _)| other.clone())
+        .filter(|other| other != id)
+        .collect();
+    let new_id = unique_slug(&slug, &taken);
+    if new_id == id {
+        return Ok(None);
+    }
+    ensure_not_reserved(&new_id)?;
+
+    let renames = HashMap::from([(id.to_string(), new_id.clone())]);
+    let mut txn = Transaction::new();
+    for (other, path) in &notes {
+        if other == id {
+            continue;
+        }
+        let other_content = read_note_content(path)?;
+        let updated = links::rewrite_links(&other_content, &renames);
+        if updated != other_content {
+            txn.write(path, updated);
+        }
+    }
+    let path = locate_note(dir, id);
+    let target = note_path(path.parent().unwrap_or(dir), &new_id);
+    txn.write(&path, links::rewrite_links(content, &renames));
+    txn.rename(&path, &target);
+    stage_note_state(app, dir, &mut txn, &[(path, target)], &renames)?;
+    txn.commit()?;
+    Ok(Some(new_id))
+}
+
+fn id_issues(
+    app: &tauri::AppHandle,
+    dir: &Path,
+) -> Result<Vec<(IdIssue, PathBuf, String)>, String> {
+    let mut issues = Vec::new();
+    for (id, path) in library_notes(app, dir)? {
+        let content = read_note_content(&path)?;
+        let Some(frontmatter_id) = frontmatter::field(&content, "id") else {
+            continue;
+        };
+        if frontmatter_id != id {
+            issues.push((IdIssue { id, frontmatter_id }, path, content));
+        }
+    }
+    issues.sort_by(|a, b| a.0.id.cmp(&b.0.id));
+    Ok(issues)
+}
+
+#[tauri::command]
+pub fn audit_ids(app: tauri::AppHandle) -> Result<Vec<IdIssue>, String> {
+    let dir = ensure_notes_dir(&app)?;
+    Ok(id_issues(&app, &dir)?
+        .into_iter()
+        .map(|(issue, _, _)| issue)
+        .collect())
+}
+
+/// Rewrites mismatched `id` fields to the filename stem, which is what every
+/// command and wiki link resolves against.
+#[tauri::command]
+pub fn fix_ids(app: tauri::AppHandle) -> Result<Vec<IdIssue>, String> {
+    let dir = ensure_notes_dir(&app)?;
+
+    let mut fixed = Vec::new();
+    for (issue, path, content) in id_issues(&app, &dir)? {
+        let updated = frontmatter::set_field(&content, "id", Some(&issue.id));
+        fs::write(&path, updated)
+            .map_err(|e| format!("Failed to write note file ({}): {e}", issue.id))?;
+        fixed.push(issue);
+    }
+    if !fixed.is_empty() {
+        cache::clear(&app);
+    }
+    Ok(fixed)
+}
+
+/// Short, URL-friendly handle for a note: the first 8 hex digits of its id's
+/// hash, lengthened only as far as needed to be unique in the library.
+#[tauri::command]
+pub fn short_id(app: tauri::AppHandle, id: String) -> Result<String, String> {
+    let dir = ensure_notes_dir(&app)?;
+    existing_note_path(&dir, &id)?;
+    let id = sanitize_id(&id);
+
+    let full = hash::hex_hash(id.as_bytes());
+    let others: Vec<String> = library_notes(&app, &dir)?
+        .into_iter()
+        .filter(|(other, _)| *other != id)
+        .map(|(other, _)| hash::hex_hash(other.as_bytes()))
+        .collect();
+
+    let len = (SHORT_ID_LEN..full.len())
+        .find(|&len| !others.iter().any(|other| other[..len] == full[..len]))
+        .unwrap_or(full.len());
+    Ok(full[..len].to_string())
+}
+
+/// Every note whose short id starts with `short`. More than one entry means
+/// the short id is ambiguous and the caller has to pick.
+#[tauri::command]
+pub fn resolve_short_id(app: tauri::AppHandle, short: String) -> Result<Vec<String>, String> {
+    let short = short.trim().to_ascii_lowercase();
+    if short.is_empty() || !short.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("Short id must be a non-empty hex string".to_string());
+    }
+
+    let dir = ensure_notes_dir(&app)?;
+    let mut candidates: Vec<String> = library_notes(&app, &dir)?
+        .into_iter()
+        .map(|(id, _)| id)
+        .filter(|id| hash::hex_hash(id.as_bytes()).starts_with(&short))
+        .collect();
+    candidates.sort();
+    Ok(candidates)
+}
@@ -0,0 +1,251 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cache, ensure_notes_dir,
+    export::{LibraryNote, Location},
+    frontmatter, generate_id, ids, links, locate_note, metadata, note_path, sanitize_id, watcher,
+};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictMode {
+    /// Keep the existing note, drop the imported one.
+    Skip,
+    /// Replace the existing note with the imported one.
+    Overwrite,
+    /// Import under a fresh `<id>_<n>` id.
+    Rename,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub created: usize,
+    pub overwritten: usize,
+    pub renamed: usize,
+    pub skipped: usize,
+    /// Human-readable reason for every entry that could not be imported.
+    pub failures: Vec<String>,
+}
+
+/// First `<id>_<n>` that isn't taken in `dir`.
+pub fn free_id(dir: &Path, id: &str) -> String {
+    (1..)
+        .map(|n| sanitize_id(&format!("{id}_{n}")))
+        .find(|candidate| !note_path(dir, candidate).exists())
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Creates a new note file for `id` (or the next free `<id>_<n>` when taken)
+/// and returns the id actually used.
+pub fn write_new_note(dir: &Path, id: &str, content: &str) -> Result<String, String> {
+    ids::ensure_not_reserved(id)?;
+    let id = if note_path(dir, id).exists() {
+        free_id(dir, id)
+    } else {
+        id.to_string()
+    };
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(note_path(dir, &id))
+        .map_err(|e| format!("Failed to create note file ({id}): {e}"))?;
+    use std::io::Write;
+    file.write_all(content.as_bytes())
+        .map_err(|e| format!("Failed to write note ({id}): {e}"))?;
+    Ok(id)
+}
+
+/// The note's content with the metadata its export listed separately (as a
+/// sidecar-storage export does) written into the frontmatter where missing.
+/// Untitled notes are exported with their id as title; that isn't added.
+fn with_metadata(note: &LibraryNote) -> String {
+    let mut content = note.content.clone();
+    if let Some(title) = note.title.as_deref().filter(|t| *t != note.id) {
+        if frontmatter::field(&content, "title").is_none() {
+            content = frontmatter::set_field(&content, "title", Some(title));
+        }
+    }
+    if !note.tags.is_empty() && frontmatter::field(&content, "tags").is_none() {
+        let tags = frontmatter::inline_list(&note.tags);
+        content = frontmatter::set_raw(&content, "tags", Some(&tags));
+    }
+    if note.pinned && frontmatter::field(&content, "pinned").is_none() {
+        content = frontmatter::set_field(&content, "pinned", Some("true"));
+    }
+    if let Some(color) = &note.color {
+        if frontmatter::field(&content, "color").is_none() {
+            content = frontmatter::set_field(&content, "color", Some(color));
+        }
+    }
+    content
+}
+
+fn parse_library(raw: &str) -> Result<Vec<LibraryNote>, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(raw).map_err(|e| format!("Invalid library JSON: {e}"))?;
+    let serde_json::Value::Array(entries) = value else {
+        return Err("Invalid library JSON: expected an array of notes".to_string());
+    };
+
+    // Validate everything up front so a bad entry can't leave a half import.
+    let mut notes = Vec::with_capacity(entries.len());
+    for (i, entry) in entries.into_iter().enumerate() {
+        let note: LibraryNote = serde_json::from_value(entry)
+            .map_err(|e| format!("Invalid library JSON: entry {i}: {e}"))?;
+        if note.id.is_empty() || sanitize_id(&note.id) != note.id {
+            return Err(format!(
+                "Invalid library JSON: entry {i} has an invalid id {:?}",
+                note.id
+            ));
+        }
+        ids::ensure_not_reserved(&note.id)
+            .map_err(|e| format!("Invalid library JSON: entry {i}: {e}"))?;
+        notes.push(note);
+    }
+    Ok(notes)
+}
+
+#[tauri::command]
+pub fn import_library_json(
+    app: tauri::AppHandle,
+    source: String,
+    on_conflict: ConflictMode,
+) -> Result<ImportSummary, String> {
+    let raw =
+        fs::read_to_string(&source).map_err(|e| format!("Failed to read library JSON: {e}"))?;
+    let notes = parse_library(&raw)?;
+    let dir = ensure_notes_dir(&app)?;
+    let storage = metadata::active(&app)?;
+    let _paused = watcher::pause(&app);
+
+    let mut summary = ImportSummary::default();
+    for note in notes {
+        let folder: PathBuf = match note.location.subdir() {
+            Some(sub) => dir.join(sub),
+            None => dir.clone(),
+        };
+        if note.location != Location::Notes {
+            fs::create_dir_all(&folder)
+                .map_err(|e| format!("Failed to create {}: {e}", folder.display()))?;
+        }
+
+        // With the nested layout a library note may sit in any folder; an
+        // overwrite replaces it there rather than adding a second copy.
+        let library = note.location == Location::Notes;
+        let find = |id: &str| {
+            if library {
+                locate_note(&dir, id)
+            } else {
+                note_path(&folder, id)
+            }
+        };
+        let mut path = find(&note.id);
+        let existed = path.exists();
+        if existed {
+            match on_conflict {
+                ConflictMode::Skip => {
+                    summary.skipped += 1;
+                    continue;
+                }
+                ConflictMode::Overwrite => {}
+                ConflictMode::Rename => {
+                    let id = (1..)
+                        .map(|n| sanitize_id(&format!("{}_{n}", note.id)))
+                        .find(|candidate| !find(candidate).exists())
+                        .unwrap_or_else(|| note.id.clone());
+                    path = note_path(&folder, &id);
+                }
+            }
+        }
+
+        let written = fs::write(&path, with_metadata(&note))
+            .map_err(|e| format!("failed to write note: {e}"))
+            .and_then(|()| metadata::adopt(storage, &path));
+        if let Err(e) = written {
+            summary.failures.push(format!("{}: {e}", note.id));
+            continue;
+        }
+        match (existed, on_conflict) {
+            (false, _) => summary.created += 1,
+            (true, ConflictMode::Rename) => summary.renamed += 1,
+            (true, _) => summary.overwritten += 1,
+        }
+    }
+
+    cache::clear(&app);
+    links::invalidate(&app);
+    Ok(summary)
+}
+
+fn column(headers: &csv::StringRecord, name: &str) -> Result<usize, String> {
+    headers
+        .iter()
+        .position(|header| header.trim() == name.trim())
+        .ok_or_else(|| format!("CSV has no column named {name:?}"))
+}
+
+/// One note per CSV row: `title_col` becomes the frontmatter title and
+/// `body_col` the content. Quoted cells may span several lines.
+#[tauri::command]
+pub fn import_csv(
+    app: tauri::AppHandle,
+    source: String,
+    title_col: String,
+    body_col: String,
+) -> Result<ImportSummary, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(&source)
+        .map_err(|e| format!("Failed to open CSV: {e}"))?;
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("Failed to read CSV header: {e}"))?
+        .clone();
+    let title_idx = column(&headers, &title_col)?;
+    let body_idx = column(&headers, &body_col)?;
+
+    let dir = ensure_notes_dir(&app)?;
+    let storage = metadata::active(&app)?;
+    let _paused = watcher::pause(&app);
+    let mut summary = ImportSummary::default();
+    for (i, record) in reader.records().enumerate() {
+        // Row 1 is the header.
+        let row = i + 2;
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                summary.skipped += 1;
+                summary.failures.push(format!("row {row}: {e}"));
+                continue;
+            }
+        };
+        let (Some(title), Some(body)) = (record.get(title_idx), record.get(body_idx)) else {
+            summary.skipped += 1;
+            summary.failures.push(format!("row {row}: missing column"));
+            continue;
+        };
+
+        let title = title.trim();
+        let content = if title.is_empty() {
+            body.to_string()
+        } else {
+            frontmatter::set_field(body, "title", Some(title))
+        };
+        let id = generate_id(&app, &dir, Some(title).filter(|t| !t.is_empty()))?;
+        let written = write_new_note(&dir, &id, &content)
+            .and_then(|id| metadata::adopt(storage, &note_path(&dir, &id)));
+        match written {
+            Ok(()) => summary.created += 1,
+            Err(e) => summary.failures.push(format!("row {row}: {e}")),
+        }
+    }
+
+    cache::clear(&app);
+    links::invalidate(&app);
+    Ok(summary)
+}
@@ -0,0 +1,79 @@
+use std::{collections::BTreeMap, fs};
+
+use crate::{
+    cache, ensure_disk_space, ensure_notes_dir, git, history, ids, library_notes, links,
+    locate_note, metadata, note_record, note_title, sanitize_id, tags, views, NoteRecord,
+};
+
+/// Heading for notes without any tag; sorted after all real tags.
+const UNTAGGED: &str = "Untagged";
+
+/// (Re)writes note `id` as a table of contents: wiki links to every other
+/// note, grouped by tag. A note with several tags is listed under each.
+#[tauri::command]
+pub fn generate_index(app: tauri::AppHandle, id: String) -> Result<NoteRecord, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let id = sanitize_id(&id);
+    if id.is_empty() {
+        return Err("Index note id must not be empty".to_string());
+    }
+
+    let mut groups: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+    let mut untagged = Vec::new();
+    let storage = metadata::active(&app)?;
+    for (note, path) in library_notes(&app, &dir)? {
+        if note == id {
+            continue;
+        }
+        let content = metadata::read_view(storage, &path)?;
+        let title = note_title(&note, &content);
+        let note_tags = tags::note_tags(&content);
+        if note_tags.is_empty() {
+            untagged.push((title, note));
+            continue;
+        }
+        for tag in note_tags {
+            groups
+                .entry(tag)
+                .or_default()
+                .push((title.clone(), note.clone()));
+        }
+    }
+
+    let mut index = String::from("# Index\n");
+    let sections = groups
+        .into_iter()
+        .chain((!untagged.is_empty()).then(|| (UNTAGGED.to_string(), untagged)));
+    for (heading, mut notes) in sections {
+        notes.sort_by(|a, b| {
+            a.0.to_lowercase()
+                .cmp(&b.0.to_lowercase())
+                .then_with(|| a.1.cmp(&b.1))
+        });
+        index.push_str(&format!("\n## {heading}\n\n"));
+        for (title, note) in notes {
+            // Brackets or a pipe in the title would end the link early.
+            let title = title.replace(['[', ']', '|'], "");
+            index.push_str(&format!("- [[{note}|{title}]]\n"));
+        }
+    }
+
+    let path = locate_note(&dir, &id);
+    if !path.exists() {
+        ids::ensure_not_reserved(&id)?;
+    }
+    ensure_disk_space(&app, &dir, index.len())?;
+    if let Ok(previous) = fs::read(&path) {
+        if previous != index.as_bytes() {
+            if let Err(e) = history::record(&app, &dir, &id, &previous) {
+                eprintln!("Failed to record history for {id}: {e}");
+            }
+        }
+    }
+
+    fs::write(&path, index).map_err(|e| format!("Failed to write note file: {e}"))?;
+    cache::invalidate(&app, &id);
+    links::invalidate(&app);
+    git::autocommit(&app, &path, &format!("Update {id}"));
+    note_record(id, &path, &views::load(&app)?, metadata::active(&app)?)
+}
@@ -0,0 +1,239 @@
+// Where note files live. `Flat` keeps every note directly in the notes dir;
+// `Nested` files them into folders. Ids stay the file name either way, so
+// wiki links keep working across a switch without being rewritten.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use crate::{
+    cache, ensure_notes_dir, existing_note_path, ids, links,
+    metadata::{self, MetadataStorage},
+    note_files, note_path, read_note_content, sanitize_id, settings, sidecar, tags,
+    transaction::Transaction,
+    tree, watcher,
+};
+
+// Folder each note had before the library was last flattened, so switching
+// back to nested puts it where it was rather than where its tag says.
+const LAYOUT_FILE: &str = "layout.json";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Layout {
+    #[default]
+    Flat,
+    Nested,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LayoutMove {
+    /// Id after the move; only differs from the old one on a name clash.
+    id: String,
+    /// Paths relative to the notes dir, `/`-separated.
+    from: String,
+    to: String,
+}
+
+/// Folder for a note without a remembered one: its first tag, one folder
+/// per `/`-separated level. `None` for untagged notes.
+fn tag_folder(content: &str) -> Option<String> {
+    let tag = tags::note_tags(content).into_iter().next()?;
+    let parts: Vec<String> = tag
+        .split('/')
+        .map(ids::slugify)
+        .filter(|part| !part.is_empty())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+fn nest(storage: MetadataStorage, dir: &Path) -> Result<Vec<LayoutMove>, String> {
+    let layout_path = dir.join(LAYOUT_FILE);
+    let mut remembered: HashMap<String, String> = sidecar::load(&layout_path)?;
+
+    let mut moves = Vec::new();
+    let mut txn = Transaction::new();
+    for (id, path) in note_files(dir)? {
+        let folder = match remembered.remove(&id) {
+            Some(folder) => Some(folder),
+            None => tag_folder(&metadata::read_view(storage, &path)?),
+        };
+        // Remembered folders come from disk, but keep them inside the library.
+        let Some(folder) = folder.filter(|f| !f.split('/').any(|p| p.is_empty() || p == ".."))
+        else {
+            continue;
+        };
+        let target = note_path(&dir.join(&folder), &id);
+        if target.exists() {
+            continue;
+        }
+        fs::create_dir_all(dir.join(&folder))
+            .map_err(|e| format!("Failed to create folder {folder}: {e}"))?;
+        txn.rename(&path, &target);
+        metadata::stage_move(&mut txn, &path, &target);
+        moves.push(LayoutMove {
+            from: format!("{id}.md"),
+            to: format!("{folder}/{id}.md"),
+            id,
+        });
+    }
+    txn.commit()?;
+    sidecar::save(&layout_path, &remembered)?;
+    Ok(moves)
+}
+
+fn flatten(app: &tauri::AppHandle, dir: &Path) -> Result<Vec<LayoutMove>, String> {
+    let layout_path = dir.join(LAYOUT_FILE);
+    let mut remembered: HashMap<String, String> = sidecar::load(&layout_path)?;
+
+    let mut taken: HashSet<String> = note_files(dir)?.into_iter().map(|(id, _)| id).collect();
+    let mut nested: Vec<(String, String)> = tree::nested_notes(dir)?
+        .into_iter()
+        .filter(|(_, rel)| rel.contains('/'))
+        .collect();
+    nested.sort_by(|a, b| a.1.cmp(&b.1));
+
+    // Notes sharing an id with one already in the root get `<id>_<n>`. Links
+    // from their own folder meant them, so only those are pointed at the
+    // new id.
+    let mut plans = Vec::new();
+    for (id, rel) in nested {
+        let folder = rel
+            .rsplit_once('/')
+            .map(|(f, _)| f.to_string())
+            .unwrap_or_default();
+        let new_id = if taken.contains(&id) {
+            (1..)
+                .map(|n| sanitize_id(&format!("{id}_{n}")))
+                .find(|candidate| !taken.contains(candidate))
+                .unwrap_or_else(|| id.clone())
+        } else {
+            id.clone()
+        };
+        taken.insert(new_id.clone());
+        plans.push((id, new_id, folder, rel));
+    }
+
+    let mut renames_by_folder: HashMap<&str, HashMap<String, String>> = HashMap::new();
+    for (id, new_id, folder, _) in &plans {
+        if id != new_id {
+            renames_by_folder
+                .entry(folder.as_str())
+                .or_default()
+                .insert(id.clone(), new_id.clone());
+        }
+    }
+
+    // History and reading state are keyed by id alone, so notes that shared
+    // one also shared it; it goes with the first renamed copy.
+    let mut renamed: HashMap<String, String> = HashMap::new();
+    let mut renamed_files = Vec::new();
+    let mut moves = Vec::new();
+    let mut txn = Transaction::new();
+    for (id, new_id, folder, rel) in &plans {
+        let path = dir.join(rel);
+        if let Some(renames) = renames_by_folder.get(folder.as_str()) {
+            let content = read_note_content(&path)?;
+            let updated = links::rewrite_links(&content, renames);
+            if updated != content {
+                txn.write(&path, updated);
+            }
+        }
+        let target = note_path(dir, new_id);
+        txn.rename(&path, &target);
+        if id != new_id && !renamed.contains_key(id) {
+            renamed.insert(id.clone(), new_id.clone());
+            renamed_files.push((path.clone(), target.clone()));
+        } else {
+            metadata::stage_move(&mut txn, &path, &target);
+        }
+        remembered.insert(new_id.clone(), folder.clone());
+        moves.push(LayoutMove {
+            id: new_id.clone(),
+            from: rel.clone(),
+            to: format!("{new_id}.md"),
+        });
+    }
+    ids::stage_note_state(app, dir, &mut txn, &renamed_files, &renamed)?;
+    txn.commit()?;
+    sidecar::save(&layout_path, &remembered)?;
+    Ok(moves)
+}
+
+/// Switches the storage layout and moves the files to match. Going nested,
+/// each root note moves to the folder it had when the library was last
+/// flattened, else to one named after its first tag (untagged notes stay in
+/// the root). Going flat, every note moves back to the root. Returns the
+/// moves made; switching to the current layout moves nothing.
+#[tauri::command]
+pub fn set_storage_layout(
+    app: tauri::AppHandle,
+    layout: Layout,
+) -> Result<Vec<LayoutMove>, String> {
+    let mut current = settings::load(&app)?;
+    if current.storage_layout == layout {
+        return Ok(Vec::new());
+    }
+    let dir = ensure_notes_dir(&app)?;
+
+    let _paused = watcher::pause(&app);
+    let moves = match layout {
+        Layout::Nested => nest(metadata::active(&app)?, &dir)?,
+        Layout::Flat => flatten(&app, &dir)?,
+    };
+    current.storage_layout = layout;
+    settings::save(&app, &current)?;
+    cache::clear(&app);
+    links::invalidate(&app);
+    // Folders are only watched with the nested layout.
+    if let Err(e) = watcher::start(&app, &dir) {
+        eprintln!("{e}");
+    }
+    Ok(moves)
+}
+
+/// Moves note `id` into `folder` (relative to the notes dir, `/`-separated;
+/// empty for the root), creating it as needed. Folders need the nested
+/// layout; with the flat one every note stays in the root. The id doesn't
+/// change, so links keep working. Returns the note's new relative path.
+#[tauri::command]
+pub fn move_note(app: tauri::AppHandle, id: String, folder: String) -> Result<String, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    let id = sanitize_id(&id);
+
+    let folder = folder.trim().trim_matches('/').to_string();
+    if !folder.is_empty() {
+        if settings::load(&app)?.storage_layout == Layout::Flat {
+            return Err("Folders need the nested storage layout".to_string());
+        }
+        // Dot folders are internal storage (.trash, .history, ...).
+        if folder
+            .split('/')
+            .any(|part| part.trim().is_empty() || part.starts_with('.') || part.contains('\\'))
+        {
+            return Err(format!("Invalid folder: {folder}"));
+        }
+    }
+
+    let target = note_path(&dir.join(&folder), &id);
+    let rel = if folder.is_empty() {
+        format!("{id}.md")
+    } else {
+        format!("{folder}/{id}.md")
+    };
+    if target == path {
+        return Ok(rel);
+    }
+    if target.exists() {
+        return Err(format!("A note named {id} already exists in that folder"));
+    }
+    fs::create_dir_all(dir.join(&folder))
+        .map_err(|e| format!("Failed to create folder {folder}: {e}"))?;
+    fs::rename(&path, &target).map_err(|e| format!("Failed to move note ({id}): {e}"))?;
+    metadata::move_with(&path, &target)?;
+    cache::invalidate(&app, &id);
+    Ok(rel)
+}
@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{ensure_notes_dir, is_md_file, sidecar};
+
+const META_FILE: &str = "meta.json";
+
+/// Layout version of the notes dir this build reads and writes.
+/// Bump it together with a new step in [`migrate`].
+pub const fn current_schema_version() -> u32 {
+    1
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Meta {
+    /// 0 means the library predates `meta.json`.
+    pub schema_version: u32,
+    /// Last id handed out by the `Sequential` id strategy.
+    pub last_sequence: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LibraryVersion {
+    /// Version recorded in the library's `meta.json`.
+    library: u32,
+    /// Version this build of the app expects.
+    app: u32,
+}
+
+fn meta_path(dir: &Path) -> PathBuf {
+    dir.join(META_FILE)
+}
+
+pub fn load_meta(dir: &Path) -> Result<Meta, String> {
+    sidecar::load(&meta_path(dir))
+}
+
+pub fn save_meta(dir: &Path, meta: &Meta) -> Result<(), String> {
+    sidecar::save(&meta_path(dir), meta)
+}
+
+/// v0 -> v1: notes are addressed as `<id>.md`, but older builds listed any
+/// casing of the extension (`.MD`, `.Md`). Rename those so every command finds
+/// them on case-sensitive filesystems.
+fn migrate_extensions(dir: &Path) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read notes dir: {e}"))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read notes dir entry: {e}"))?;
+        let path = entry.path();
+        if !path.is_file() || !is_md_file(&path) {
+            continue;
+        }
+        if path.extension().and_then(|e| e.to_str()) == Some("md") {
+            continue;
+        }
+
+        let target = path.with_extension("md");
+        if target.exists() {
+            eprintln!(
+                "migration: leaving {} in place, {} already exists",
+                path.display(),
+                target.display()
+            );
+            continue;
+        }
+        fs::rename(&path, &target)
+            .map_err(|e| format!("Failed to rename {}: {e}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Brings the library at `dir` up to [`current_schema_version`]. Each step is
+/// idempotent and the version is saved after every step, so an interrupted
+/// migration simply resumes on the next start.
+pub fn migrate(dir: &Path) -> Result<(), String> {
+    let mut meta = load_meta(dir)?;
+    let target = current_schema_version();
+    if meta.schema_version > target {
+        return Err(format!(
+            "Notes library has schema version {} but this app only supports up to {target}",
+            meta.schema_version
+        ));
+    }
+
+    while meta.schema_version < target {
+        match meta.schema_version {
+            0 => migrate_extensions(dir)?,
+            v => return Err(format!("No migration from schema version {v}")),
+        }
+        meta.schema_version += 1;
+        save_meta(dir, &meta)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn library_version(app: tauri::AppHandle) -> Result<LibraryVersion, String> {
+    let dir = ensure_notes_dir(&app)?;
+    Ok(LibraryVersion {
+        library: load_meta(&dir)?.schema_version,
+        app: current_schema_version(),
+    })
+}
@@ -0,0 +1,719 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs,
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+};
+use tauri::Manager;
+
+use crate::{
+    ensure_notes_dir, existing_note_path, frontmatter, library_notes, locate_note,
+    markdown::{is_fence, lines_with_fences},
+    metadata, note_title, pin, read_note_content, sanitize_id, ATTACHMENTS_DIR, HISTORY_DIR,
+};
+
+/// Each note id mapped to the distinct ids it links to.
+pub type LinkMatrix = HashMap<String, Vec<String>>;
+
+/// The last built [`LinkMatrix`], dropped whenever a note changes.
+#[derive(Default)]
+pub struct LinkCache(Mutex<Option<Arc<LinkMatrix>>>);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinkTarget {
+    /// Display title of the target note; `None` when the link is broken.
+    title: Option<String>,
+    broken: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RenderedNote {
+    id: String,
+    /// Note content without its frontmatter block.
+    body: String,
+    /// Every wiki-link target in the body, keyed by target id.
+    links: HashMap<String, LinkTarget>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteImpact {
+    id: String,
+    /// Notes containing at least one `[[id]]` link to this note.
+    linked_from: Vec<String>,
+    /// Attachments embedded by this note that exist on disk.
+    attachments: usize,
+    /// Stored history versions of this note.
+    history: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinkHealth {
+    id: String,
+    /// Distinct link targets that exist.
+    valid: usize,
+    /// Distinct link targets with no note.
+    broken: usize,
+    /// Share of targets that exist, 0-100; 100 for notes without links.
+    score: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinkIssue {
+    /// Note containing the link.
+    source: String,
+    /// Target as written inside `[[...]]`.
+    target: String,
+    /// What the target sanitizes to; no note has this id.
+    sanitized: String,
+    /// Closest existing id, when one is near enough to be a likely fix.
+    suggestion: Option<String>,
+}
+
+/// Raw targets of every `[[target]]`, `[[target|alias]]` and `[[target#heading]]`
+/// link in `content`, in order of appearance. Links inside code fences are ignored.
+pub fn wiki_links(content: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    for (line, in_fence) in lines_with_fences(content) {
+        if in_fence {
+            continue;
+        }
+
+        let mut rest = line;
+        while let Some(start) = rest.find("[[") {
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("]]") else {
+                break;
+            };
+
+            let inner = &after[..end];
+            let target = inner.split(['|', '#']).next().unwrap_or_default().trim();
+            if !target.is_empty() {
+                out.push(target.to_string());
+            }
+            rest = &after[end + 2..];
+        }
+    }
+    out
+}
+
+/// Calls `replace` with the trimmed target and the full inner text of every
+/// wiki link outside code fences; a returned string replaces the whole
+/// `[[...]]`, `None` keeps the link as it was.
+pub fn replace_links(
+    content: &str,
+    mut replace: impl FnMut(&str, &str) -> Option<String>,
+) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut in_fence = false;
+    for line in content.split_inclusive('\n') {
+        if is_fence(line) {
+            in_fence = !in_fence;
+        }
+        if in_fence || is_fence(line) {
+            out.push_str(line);
+            continue;
+        }
+
+        let mut rest = line;
+        while let Some(start) = rest.find("[[") {
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("]]") else {
+                break;
+            };
+            out.push_str(&rest[..start]);
+
+            let inner = &after[..end];
+            let target = inner.split(['|', '#']).next().unwrap_or_default().trim();
+            match replace(target, inner).filter(|_| !target.is_empty()) {
+                Some(replacement) => out.push_str(&replacement),
+                None => {
+                    out.push_str("[[");
+                    out.push_str(inner);
+                    out.push_str("]]");
+                }
+            }
+            rest = &after[end + 2..];
+        }
+        out.push_str(rest);
+    }
+    out
+}
+
+/// Rewrites the target of every wiki link whose id appears in `renames`,
+/// keeping aliases and heading anchors as they were.
+pub fn rewrite_links(content: &str, renames: &HashMap<String, String>) -> String {
+    replace_links(content, |target, inner| {
+        let new_id = renames.get(&sanitize_id(target))?;
+        let split = inner.find(['|', '#']).unwrap_or(inner.len());
+        Some(format!("[[{new_id}{}]]", &inner[split..]))
+    })
+}
+
+/// Distinct link targets of `content` mapped onto note ids, in order of
+/// first appearance.
+pub fn link_ids(content: &str) -> Vec<String> {
+    let mut ids: Vec<String> = Vec::new();
+    for target in wiki_links(content) {
+        let id = sanitize_id(&target);
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+/// Destinations of markdown links/embeds (`[..](dest)`, `![..](dest)`) that
+/// point into the attachments folder, without any leading `./`.
+pub fn attachment_refs(content: &str) -> Vec<String> {
+    link_destinations(content)
+        .into_iter()
+        .filter(|dest| dest.starts_with(&format!("{ATTACHMENTS_DIR}/")))
+        .collect()
+}
+
+/// Every markdown link/embed destination outside code fences, without its
+/// title, angle brackets or a leading `./`.
+fn link_destinations(content: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    for (line, in_fence) in lines_with_fences(content) {
+        if in_fence {
+            continue;
+        }
+
+        let mut rest = line;
+        while let Some(start) = rest.find("](") {
+            let after = &rest[start + 2..];
+            let Some(end) = after.find(')') else {
+                break;
+            };
+
+            // Drop an optional title: `](path "title")`.
+            let dest = after[..end].split_whitespace().next().unwrap_or_default();
+            let dest = dest.trim_start_matches('<').trim_end_matches('>');
+            out.push(dest.trim_start_matches("./").to_string());
+            rest = &after[end + 1..];
+        }
+    }
+    out
+}
+
+fn build_matrix(app: &tauri::AppHandle, dir: &Path) -> Result<LinkMatrix, String> {
+    let mut matrix = LinkMatrix::new();
+    for (id, path) in library_notes(app, dir)? {
+        matrix.insert(id, link_ids(&read_note_content(&path)?));
+    }
+    Ok(matrix)
+}
+
+/// The link matrix of the whole library, built in one pass over the notes and
+/// served from memory until [`invalidate`] is called.
+pub fn matrix(app: &tauri::AppHandle) -> Result<Arc<LinkMatrix>, String> {
+    let cache = app.state::<LinkCache>();
+    let mut cached = cache.0.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(matrix) = cached.as_ref() {
+        return Ok(Arc::clone(matrix));
+    }
+
+    let dir = ensure_notes_dir(app)?;
+    let matrix = Arc::new(build_matrix(app, &dir)?);
+    *cached = Some(Arc::clone(&matrix));
+    Ok(matrix)
+}
+
+pub fn invalidate(app: &tauri::AppHandle) {
+    let cache = app.state::<LinkCache>();
+    *cache.0.lock().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+fn count_files(dir: &Path) -> usize {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|e| e.path().is_file())
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+#[tauri::command]
+pub fn delete_impact(app: tauri::AppHandle, id: String) -> Result<DeleteImpact, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    let id = sanitize_id(&id);
+
+    let mut linked_from = Vec::new();
+    for (other, other_path) in library_notes(&app, &dir)? {
+        if other == id {
+            continue;
+        }
+        let content = read_note_content(&other_path)?;
+        if link_ids(&content).contains(&id) {
+            linked_from.push(other);
+        }
+    }
+    linked_from.sort();
+
+    let content = read_note_content(&path)?;
+    let mut attachments = attachment_refs(&content);
+    attachments.sort();
+    attachments.dedup();
+    let attachments = attachments
+        .iter()
+        .filter(|rel| dir.join(rel).is_file())
+        .count();
+
+    let history = count_files(&dir.join(HISTORY_DIR).join(&id));
+
+    Ok(DeleteImpact {
+        id,
+        linked_from,
+        attachments,
+        history,
+    })
+}
+
+/// Where `reference` (a link destination or a path the user gave) points
+/// inside the attachments folder, as `.attachments/...`. Absolute paths into
+/// the notes dir, `file://` URLs and root-relative `/.attachments/...` all
+/// count; so does a bare name inside the folder.
+pub fn attachment_key(dir: &Path, reference: &str) -> Option<String> {
+    let reference = reference.trim().replace('\\', "/");
+    let reference = reference.strip_prefix("file://").unwrap_or(&reference);
+    let notes_dir = dir.to_string_lossy().replace('\\', "/");
+    let rel = reference
+        .strip_prefix(notes_dir.trim_end_matches('/'))
+        .unwrap_or(reference)
+        .trim_start_matches('/')
+        .trim_start_matches("./");
+    if rel.is_empty() || rel.split('/').any(|part| part == "..") {
+        return None;
+    }
+    if rel.starts_with(&format!("{ATTACHMENTS_DIR}/")) {
+        Some(rel.to_string())
+    } else {
+        None
+    }
+}
+
+/// Points every link or embed whose destination resolves (per
+/// [`attachment_key`]) to a key of `moves` at its value instead, keeping
+/// titles and angle brackets. Code fences are left alone.
+pub fn rewrite_attachment_refs(
+    dir: &Path,
+    content: &str,
+    moves: &HashMap<String, String>,
+) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut in_fence = false;
+    for line in content.split_inclusive('\n') {
+        if is_fence(line) {
+            in_fence = !in_fence;
+        }
+        if in_fence || is_fence(line) {
+            out.push_str(line);
+            continue;
+        }
+
+        let mut rest = line;
+        while let Some(start) = rest.find("](") {
+            let after = &rest[start + 2..];
+            let Some(end) = after.find(')') else {
+                break;
+            };
+            out.push_str(&rest[..start + 2]);
+
+            let dest = &after[..end];
+            let token = dest.split_whitespace().next().unwrap_or_default();
+            let path = token.trim_start_matches('<').trim_end_matches('>');
+            match attachment_key(dir, path).and_then(|key| moves.get(&key)) {
+                Some(target) => out.push_str(&dest.replacen(path, target, 1)),
+                None => out.push_str(dest),
+            }
+            out.push(')');
+            rest = &after[end + 1..];
+        }
+        out.push_str(rest);
+    }
+    out
+}
+
+/// Notes whose links or embeds point at `attachment_path`, in any of the
+/// forms [`attachment_key`] accepts.
+#[tauri::command]
+pub fn attachment_references(
+    app: tauri::AppHandle,
+    attachment_path: String,
+) -> Result<Vec<String>, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let wanted = attachment_key(&dir, &attachment_path)
+        .or_else(|| attachment_key(&dir, &format!("{ATTACHMENTS_DIR}/{attachment_path}")))
+        .ok_or_else(|| format!("Not an attachment path: {attachment_path}"))?;
+
+    let mut ids = Vec::new();
+    for (id, path) in library_notes(&app, &dir)? {
+        let content = read_note_content(&path)?;
+        if link_destinations(&content)
+            .iter()
+            .any(|dest| attachment_key(&dir, dest).as_deref() == Some(wanted.as_str()))
+        {
+            ids.push(id);
+        }
+    }
+    ids.sort();
+    Ok(ids)
+}
+
+#[tauri::command]
+pub fn link_matrix(app: tauri::AppHandle) -> Result<LinkMatrix, String> {
+    Ok(matrix(&app)?.as_ref().clone())
+}
+
+#[tauri::command]
+pub fn orphan_notes(app: tauri::AppHandle) -> Result<Vec<String>, String> {
+    let matrix = matrix(&app)?;
+
+    // Only links between two distinct, existing notes connect anything:
+    // self-links and broken links don't take a note out of isolation.
+    let mut connected: HashSet<&str> = HashSet::new();
+    for (id, targets) in matrix.iter() {
+        for target in targets {
+            if target != id && matrix.contains_key(target) {
+                connected.insert(id.as_str());
+                connected.insert(target.as_str());
+            }
+        }
+    }
+
+    let mut orphans: Vec<String> = matrix
+        .keys()
+        .filter(|id| !connected.contains(id.as_str()))
+        .cloned()
+        .collect();
+    orphans.sort();
+    Ok(orphans)
+}
+
+#[tauri::command]
+pub fn read_note_rendered(app: tauri::AppHandle, id: String) -> Result<RenderedNote, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    let content = read_note_content(&path)?;
+    let body = frontmatter::body(&content).to_string();
+
+    let storage = metadata::active(&app)?;
+    let mut targets = HashMap::new();
+    for target in link_ids(&body) {
+        let target_path = locate_note(&dir, &target);
+        let info = match metadata::read_view(storage, &target_path) {
+            Ok(target_content) => LinkTarget {
+                title: Some(note_title(&target, &target_content)),
+                broken: false,
+            },
+            Err(_) => LinkTarget {
+                title: None,
+                broken: true,
+            },
+        };
+        targets.insert(target, info);
+    }
+
+    Ok(RenderedNote {
+        id: sanitize_id(&id),
+        body,
+        links: targets,
+    })
+}
+
+/// The `limit` most linked-to notes with the number of other notes linking to
+/// each, highest first. Self-links and links to missing notes don't count.
+#[tauri::command]
+pub fn notes_by_inbound_links(
+    app: tauri::AppHandle,
+    limit: usize,
+) -> Result<Vec<(String, usize)>, String> {
+    let matrix = matrix(&app)?;
+
+    let mut inbound: HashMap<&str, usize> = matrix.keys().map(|id| (id.as_str(), 0)).collect();
+    for (id, targets) in matrix.iter() {
+        for target in targets {
+            if target != id {
+                if let Some(count) = inbound.get_mut(target.as_str()) {
+                    *count += 1;
+                }
+            }
+        }
+    }
+
+    let mut ranked: Vec<(String, usize)> = inbound
+        .into_iter()
+        .map(|(id, count)| (id.to_string(), count))
+        .collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.truncate(limit);
+    Ok(ranked)
+}
+
+/// `root` and every existing note reachable from it by following outbound
+/// wiki links, within `max_depth` hops when given, in breadth-first order.
+#[tauri::command]
+pub fn reachable_notes(
+    app: tauri::AppHandle,
+    root: String,
+    max_depth: Option<usize>,
+) -> Result<Vec<String>, String> {
+    let matrix = matrix(&app)?;
+    let root = sanitize_id(&root);
+    if !matrix.contains_key(&root) {
+        return Err(format!("Note not found: {root}"));
+    }
+
+    // Each note is queued once, so cycles end the search instead of looping.
+    let mut seen = HashSet::from([root.as_str()]);
+    let mut order = Vec::new();
+    let mut queue = VecDeque::from([(root.as_str(), 0usize)]);
+    while let Some((id, hops)) = queue.pop_front() {
+        order.push(id.to_string());
+        if max_depth.is_some_and(|max| hops >= max) {
+            continue;
+        }
+        for target in &matrix[id] {
+            if matrix.contains_key(target) && seen.insert(target.as_str()) {
+                queue.push_back((target.as_str(), hops + 1));
+            }
+        }
+    }
+    Ok(order)
+}
+
+/// Outbound link counts of every note, worst score first (ties by most
+/// broken links, then id).
+#[tauri::command]
+pub fn link_health(app: tauri::AppHandle) -> Result<Vec<LinkHealth>, String> {
+    let matrix = matrix(&app)?;
+
+    let mut health: Vec<LinkHealth> = matrix
+        .iter()
+        .map(|(id, targets)| {
+            let valid = targets.iter().filter(|t| matrix.contains_key(*t)).count();
+            let broken = targets.len() - valid;
+            let score = match targets.len() {
+                0 => 100,
+                total => (valid * 100 / total) as u8,
+            };
+            LinkHealth {
+                id: id.clone(),
+                valid,
+                broken,
+                score,
+            }
+        })
+        .collect();
+    health.sort_by(|a, b| {
+        a.score
+            .cmp(&b.score)
+            .then_with(|| b.broken.cmp(&a.broken))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    Ok(health)
+}
+
+// Libraries with dense cross-linking have an exponential number of cycles;
+// past this many the answer is "tangled" anyway.
+const MAX_CYCLES: usize = 500;
+
+/// Cycle search state for Johnson's algorithm over note indices.
+struct CycleSearch<'a> {
+    adjacency: &'a [Vec<usize>],
+    start: usize,
+    stack: Vec<usize>,
+    blocked: Vec<bool>,
+    blocked_by: Vec<HashSet<usize>>,
+    cycles: Vec<Vec<usize>>,
+    /// Set once a cycle past `MAX_CYCLES` turns up; the search stops there.
+    truncated: bool,
+}
+
+impl CycleSearch<'_> {
+    fn unblock(&mut self, node: usize) {
+        self.blocked[node] = false;
+        for other in std::mem::take(&mut self.blocked_by[node]) {
+            if self.blocked[other] {
+                self.unblock(other);
+            }
+        }
+    }
+
+    /// Records every cycle through `start` that continues from `node`, using
+    /// only nodes numbered `start` or higher. Returns whether any was found.
+    fn circuit(&mut self, node: usize) -> bool {
+        let adjacency = self.adjacency;
+        let mut found = false;
+        self.stack.push(node);
+        self.blocked[node] = true;
+        for &next in &adjacency[node] {
+            if self.truncated {
+                break;
+            }
+            if next < self.start {
+                continue;
+            }
+            if next == self.start {
+                if self.cycles.len() >= MAX_CYCLES {
+                    self.truncated = true;
+                    break;
+                }
+                self.cycles.push(self.stack.clone());
+                found = true;
+            } else if !self.blocked[next] && self.circuit(next) {
+                found = true;
+            }
+        }
+        if found {
+            self.unblock(node);
+        } else {
+            for &next in &adjacency[node] {
+                if next >= self.start {
+                    self.blocked_by[next].insert(node);
+                }
+            }
+        }
+        self.stack.pop();
+        found
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinkCycles {
+    /// Each cycle of two or more notes as the ids along it, starting from its
+    /// alphabetically smallest id; the link back to the first id is implied.
+    cycles: Vec<Vec<String>>,
+    /// Notes linking to themselves.
+    self_links: Vec<String>,
+    /// Whether there were more than the 500 cycles listed.
+    truncated: bool,
+}
+
+/// Every cycle in the wiki-link graph, with notes that link to themselves
+/// reported separately.
+#[tauri::command]
+pub fn link_cycles(app: tauri::AppHandle) -> Result<LinkCycles, String> {
+    let matrix = matrix(&app)?;
+
+    let mut ids: Vec<&String> = matrix.keys().collect();
+    ids.sort();
+    let index: HashMap<&String, usize> = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+
+    let mut self_links = Vec::new();
+    let mut adjacency = vec![Vec::new(); ids.len()];
+    for (i, id) in ids.iter().enumerate() {
+        for target in &matrix[*id] {
+            if target == *id {
+                self_links.push(target.clone());
+            } else if let Some(&j) = index.get(target) {
+                adjacency[i].push(j);
+            }
+        }
+        adjacency[i].sort_unstable();
+    }
+
+    let mut search = CycleSearch {
+        adjacency: &adjacency,
+        start: 0,
+        stack: Vec::new(),
+        blocked: vec![false; ids.len()],
+        blocked_by: vec![HashSet::new(); ids.len()],
+        cycles: Vec::new(),
+        truncated: false,
+    };
+    for start in 0..ids.len() {
+        if search.truncated {
+            break;
+        }
+        search.start = start;
+        for node in start..ids.len() {
+            search.blocked[node] = false;
+            search.blocked_by[node].clear();
+        }
+        search.circuit(start);
+    }
+
+    let cycles = search
+        .cycles
+        .into_iter()
+        .map(|cycle| cycle.into_iter().map(|i| ids[i].clone()).collect())
+        .collect();
+    Ok(LinkCycles {
+        cycles,
+        self_links,
+        truncated: search.truncated,
+    })
+}
+
+/// Edit distance between `a` and `b`, compared case-insensitively.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut row = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(ca != cb);
+            row[j + 1] = substitute.min(prev[j + 1] + 1).min(row[j] + 1);
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+/// The existing id closest to `target`, if it is within a third of the
+/// target's length (at least 2 edits) of it.
+fn closest_id<'a>(target: &str, ids: &'a [String]) -> Option<&'a String> {
+    let limit = (target.chars().count() / 3).max(2);
+    ids.iter()
+        .map(|id| (levenshtein(target, id), id))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)))
+        .map(|(_, id)| id)
+}
+
+/// Every wiki link whose target, after sanitizing, names no existing note,
+/// with the closest existing id as a suggested fix.
+#[tauri::command]
+pub fn audit_link_targets(app: tauri::AppHandle) -> Result<Vec<LinkIssue>, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let files = library_notes(&app, &dir)?;
+    let mut ids: Vec<String> = files.iter().map(|(id, _)| id.clone()).collect();
+    ids.sort();
+    let known: HashSet<&str> = ids.iter().map(String::as_str).collect();
+
+    let mut issues = Vec::new();
+    for (source, path) in &files {
+        let mut seen = HashSet::new();
+        for target in wiki_links(&read_note_content(path)?) {
+            let sanitized = sanitize_id(&target);
+            if known.contains(sanitized.as_str()) || !seen.insert(target.clone()) {
+                continue;
+            }
+            // Compare the raw target too: it may be closer to the intended
+            // id than what the dropped characters left behind.
+            let suggestion = closest_id(&target, &ids)
+                .or_else(|| closest_id(&sanitized, &ids))
+                .cloned();
+            issues.push(LinkIssue {
+                source: source.clone(),
+                target,
+                sanitized,
+                suggestion,
+            });
+        }
+    }
+    issues.sort_by(|a, b| {
+        a.source
+            .cmp(&b.source)
+            .then_with(|| a.target.cmp(&b.target))
+    });
+    Ok(issues)
+}
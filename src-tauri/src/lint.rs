@@ -0,0 +1,174 @@
+// Content that parses but will render badly: markdown renderers never fail,
+// they silently show the broken syntax as text (or swallow the rest of the
+// note into a code block). Line-based like the rest of the markdown helpers.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::{ensure_notes_dir, frontmatter, library_notes, markdown::is_fence};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RenderIssue {
+    id: String,
+    /// 1-based line in the file; 0 for problems with the file as a whole.
+    line: usize,
+    message: String,
+}
+
+/// Cells of a table row; `None` when the line isn't one.
+fn table_cells(line: &str) -> Option<usize> {
+    let trimmed = line.trim();
+    if !trimmed.contains('|') {
+        return None;
+    }
+    let inner = trimmed.strip_prefix('|').unwrap_or(trimmed);
+    let inner = inner.strip_suffix('|').unwrap_or(inner);
+    Some(inner.split('|').count())
+}
+
+fn is_delimiter_row(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.contains('-')
+        && trimmed.contains('|')
+        && trimmed
+            .chars()
+            .all(|c| matches!(c, '|' | '-' | ':' | ' ' | '\t'))
+}
+
+/// `line` with inline code spans blanked out, so their contents aren't
+/// checked as link syntax.
+fn without_code_spans(line: &str) -> String {
+    let mut in_code = false;
+    line.chars()
+        .filter(|&c| {
+            if c == '`' {
+                in_code = !in_code;
+            }
+            !in_code && c != '`'
+        })
+        .collect()
+}
+
+fn link_problems(line: &str) -> Vec<&'static str> {
+    let mut problems = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.find("[[") {
+        match rest[start + 2..].find("]]") {
+            Some(end) if rest[start + 2..start + 2 + end].trim().is_empty() => {
+                problems.push("Empty wiki link");
+                rest = &rest[start + 4 + end..];
+            }
+            Some(end) => rest = &rest[start + 4 + end..],
+            None => {
+                problems.push("Wiki link is missing its closing ]]");
+                break;
+            }
+        }
+    }
+
+    let mut rest = line;
+    while let Some(start) = rest.find("](") {
+        match rest[start + 2..].find(')') {
+            Some(end) => rest = &rest[start + 3 + end..],
+            None => {
+                problems.push("Link is missing its closing )");
+                break;
+            }
+        }
+    }
+    problems
+}
+
+fn check(id: &str, content: &str) -> Vec<RenderIssue> {
+    let mut issues = Vec::new();
+    let mut push = |line: usize, message: String| {
+        issues.push(RenderIssue {
+            id: id.to_string(),
+            line,
+            message,
+        });
+    };
+
+    let body = frontmatter::body(content);
+    let first_line = content[..content.len() - body.len()].matches('\n').count() + 1;
+    let lines: Vec<&str> = body.lines().collect();
+
+    let mut open_fence: Option<usize> = None;
+    // Expected cell count of the table being read, if any.
+    let mut table: Option<usize> = None;
+    for (i, line) in lines.iter().enumerate() {
+        let line_no = first_line + i;
+        if is_fence(line) {
+            open_fence = match open_fence {
+                Some(_) => None,
+                None => Some(line_no),
+            };
+            table = None;
+            continue;
+        }
+        if open_fence.is_some() {
+            continue;
+        }
+
+        let cells = table_cells(line);
+        match (table, cells) {
+            (_, _) if is_delimiter_row(line) => {
+                let header = i.checked_sub(1).and_then(|h| table_cells(lines[h]));
+                match (header, cells) {
+                    (Some(header), Some(delimiter)) if header != delimiter => push(
+                        line_no,
+                        format!(
+                            "Table header has {header} columns but its delimiter row has {delimiter}"
+                        ),
+                    ),
+                    (None, _) => push(line_no, "Table delimiter row without a header".to_string()),
+                    _ => {}
+                }
+                table = header;
+            }
+            (Some(expected), Some(found)) if found != expected => push(
+                line_no,
+                format!("Table row has {found} cells, expected {expected}"),
+            ),
+            (Some(_), None) => table = None,
+            _ => {}
+        }
+
+        for problem in link_problems(&without_code_spans(line)) {
+            push(line_no, problem.to_string());
+        }
+    }
+    if let Some(line_no) = open_fence {
+        push(line_no, "Code fence is never closed".to_string());
+    }
+    issues
+}
+
+/// Markdown problems in every note, in id and line order. A note that can't
+/// be read is reported as an issue instead of stopping the scan.
+#[tauri::command]
+pub fn render_errors(app: tauri::AppHandle) -> Result<Vec<RenderIssue>, String> {
+    let dir = ensure_notes_dir(&app)?;
+
+    let mut issues = Vec::new();
+    let mut files = library_notes(&app, &dir)?;
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    for (id, path) in files {
+        let problem = match fs::read(&path) {
+            Ok(bytes) => match String::from_utf8(bytes) {
+                Ok(content) => {
+                    issues.extend(check(&id, &content));
+                    continue;
+                }
+                Err(_) => "File is not valid UTF-8".to_string(),
+            },
+            Err(e) => format!("Failed to read note file: {e}"),
+        };
+        issues.push(RenderIssue {
+            id,
+            line: 0,
+            message: problem,
+        });
+    }
+    Ok(issues)
+}
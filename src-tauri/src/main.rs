@@ -8,6 +8,64 @@ use std::{
 use tauri::Manager;
 use tauri_plugin_notification::init as notification_init;
 
+mod archive;
+mod attachments;
+mod backup;
+mod blocks;
+mod cache;
+mod checkpoints;
+mod cleanup;
+mod confirm;
+mod dates;
+mod diff;
+mod docx;
+mod enex;
+mod export;
+mod feed;
+mod frontmatter;
+mod fsutil;
+mod git;
+mod hash;
+mod history;
+mod ids;
+mod import;
+mod index;
+mod layout;
+mod library;
+mod links;
+mod lint;
+mod maintenance;
+mod markdown;
+mod metadata;
+mod permalink;
+mod pin;
+mod properties;
+mod read;
+mod relocate;
+mod render;
+mod saved_searches;
+mod schema;
+mod scroll;
+mod search;
+mod settings;
+mod shell;
+mod sidecar;
+mod similarity;
+mod stats;
+mod tags;
+mod tasks;
+mod transaction;
+mod trash;
+mod tree;
+mod views;
+mod watcher;
+
+// Internal folders kept inside the notes dir next to the notes themselves.
+const ATTACHMENTS_DIR: &str = ".attachments";
+const HISTORY_DIR: &str = ".history";
+const ARCHIVE_DIR: &str = ".archive";
+const TRASH_DIR: &str = ".trash";
+
 #[derive(Debug, Serialize, Deserialize)]
 struct CreateNoteResponse {
     /// Stable identifier for the note (used as filename stem).
@@ -29,14 +87,25 @@ struct DeleteNoteRequest {
     id: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct NoteRecord {
     id: String,
     path: String,
     content: String,
+    /// Emoji or short label from the `icon` frontmatter field.
+    icon: Option<String>,
+    /// When the note will be moved to the archive (`expires_ms` field).
+    expires_ms: Option<u64>,
+    /// When the note was last opened (see `views::record_view`).
+    last_viewed_ms: Option<u64>,
+    /// Title, tags, pinned and color, from the active `metadata_storage`.
+    metadata: metadata::NoteMeta,
 }
 
 fn notes_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    if let Some(custom) = settings::load(app)?.notes_dir {
+        return Ok(PathBuf::from(custom));
+    }
     let base = app
         .path()
         .app_data_dir()
@@ -71,25 +140,17 @@ fn is_md_file(path: &Path) -> bool {
         .unwrap_or(false)
 }
 
-fn generate_id() -> String {
-    // No external crate: use milliseconds since UNIX_EPOCH.
-    // This is "unique enough" for a local notes app; frontend can treat as opaque.
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let ms = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_millis())
-        .unwrap_or(0);
-    format!("note_{ms}")
-}
-
-#[tauri::command]
-fn list_notes(app: tauri::AppHandle) -> Result<Vec<NoteRecord>, String> {
-    let dir = notes_dir(&app)?;
+fn ensure_notes_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = notes_dir(app)?;
     fs::create_dir_all(&dir).map_err(|e| format!("Failed to create notes dir: {e}"))?;
+    Ok(dir)
+}
 
-    let mut notes = Vec::new();
-    let entries = fs::read_dir(&dir).map_err(|e| format!("Failed to read notes dir: {e}"))?;
+/// Every markdown note directly inside `dir`, as `(id, path)` pairs.
+fn note_files(dir: &Path) -> Result<Vec<(String, PathBuf)>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read notes dir: {e}"))?;
 
+    let mut files = Vec::new();
     for entry in entries {
         let entry = entry.map_err(|e| format!("Failed to read notes dir entry: {e}"))?;
         let path = entry.path();
@@ -108,15 +169,177 @@ fn list_notes(app: tauri::AppHandle) -> Result<Vec<NoteRecord>, String> {
             continue;
         }
 
-        let content = fs::read_to_string(&path)
-            .map_err(|e| format!("Failed to read note content ({}): {e}", path.display()))?;
+        files.push((id, path));
+    }
+    Ok(files)
+}
+
+/// Every note of the library in `dir`: the files directly in it, plus those
+/// in its folders when the storage layout is nested.
+fn library_notes(app: &tauri::AppHandle, dir: &Path) -> Result<Vec<(String, PathBuf)>, String> {
+    Ok(match settings::load(app)?.storage_layout {
+        layout::Layout::Flat => note_files(dir)?,
+        layout::Layout::Nested => tree::nested_notes(dir)?
+            .into_iter()
+            .map(|(id, rel)| (id, dir.join(rel)))
+            .collect(),
+    })
+}
+
+/// The file of note `id`: directly in `dir`, else in one of its folders (as
+/// with the nested layout). Notes that don't exist yet belong directly in
+/// `dir`.
+fn locate_note(dir: &Path, id: &str) -> PathBuf {
+    let path = note_path(dir, id);
+    if path.is_file() {
+        return path;
+    }
+    tree::find_note(dir, id).unwrap_or(path)
+}
 
-        notes.push(NoteRecord {
-            id,
-            path: path.to_string_lossy().to_string(),
-            content,
-        });
+/// Resolves the file of a note that must already exist.
+fn existing_note_path(dir: &Path, id: &str) -> Result<PathBuf, String> {
+    let path = locate_note(dir, &sanitize_id(id));
+    if !path.is_file() {
+        return Err(format!("Note not found: {id}"));
     }
+    Ok(path)
+}
+
+fn read_note_content(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read note content ({}): {e}", path.display()))
+}
+
+/// Display title of a note: the `title` frontmatter field, else the first
+/// heading of the body, else the id.
+fn note_title(id: &str, content: &str) -> String {
+    if let Some(title) = frontmatter::field(content, "title") {
+        return title;
+    }
+    markdown::lines_with_fences(frontmatter::split(content).1)
+        .into_iter()
+        .filter(|(_, in_fence)| !in_fence)
+        .find_map(|(line, _)| markdown::heading(line))
+        .map(|(_, text)| text.to_string())
+        .filter(|text| !text.is_empty())
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Moves note `id` from `dir` (or one of its folders) into its internal
+/// `folder` (e.g. the trash), picking a fresh id there if the folder already
+/// holds one with that name.
+fn move_to_folder(dir: &Path, folder: &str, id: &str) -> Result<PathBuf, String> {
+    let folder = dir.join(folder);
+    fs::create_dir_all(&folder)
+        .map_err(|e| format!("Failed to create {}: {e}", folder.display()))?;
+
+    let mut target = note_path(&folder, id);
+    if target.exists() {
+        target = note_path(&folder, &import::free_id(&folder, id));
+    }
+    let source = locate_note(dir, id);
+    fs::rename(&source, &target).map_err(|e| format!("Failed to move note ({id}): {e}"))?;
+    metadata::move_with(&source, &target)?;
+    Ok(target)
+}
+
+fn system_time_ms(time: std::time::SystemTime) -> u64 {
+    time.duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// `(created_ms, modified_ms)` of a file. Filesystems without a birth time
+/// report the modification time for both.
+fn file_times_ms(path: &Path) -> Result<(u64, u64), String> {
+    let meta = fs::metadata(path)
+        .map_err(|e| format!("Failed to read file metadata ({}): {e}", path.display()))?;
+    let modified = meta.modified().map(system_time_ms).unwrap_or(0);
+    let created = meta.created().map(system_time_ms).unwrap_or(modified);
+    Ok((created, modified))
+}
+
+// Headroom for the filesystem and the sidecar/history writes that follow.
+const DEFAULT_DISK_SPACE_MARGIN: u64 = 10 * 1024 * 1024;
+
+/// Refuses a write of `bytes` into `dir` on a nearly full disk, before a
+/// partial file can be left behind.
+fn ensure_disk_space(app: &tauri::AppHandle, dir: &Path, bytes: usize) -> Result<(), String> {
+    let margin = settings::load(app)?
+        .disk_space_margin
+        .unwrap_or(DEFAULT_DISK_SPACE_MARGIN);
+    fsutil::ensure_space(dir, bytes as u64, margin)
+}
+
+fn timestamp_id() -> String {
+    // No external crate: use milliseconds since UNIX_EPOCH.
+    // This is "unique enough" for a local notes app; frontend can treat as opaque.
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    format!("note_{ms}")
+}
+
+/// A fresh id for a new note in `dir`, following the configured
+/// [`ids::IdStrategy`]. Every strategy yields ids `sanitize_id` leaves intact.
+fn generate_id(app: &tauri::AppHandle, dir: &Path, title: Option<&str>) -> Result<String, String> {
+    let id = match settings::load(app)?.id_strategy {
+        ids::IdStrategy::Timestamp => timestamp_id(),
+        ids::IdStrategy::Uuid => uuid::Uuid::new_v4().to_string(),
+        ids::IdStrategy::Slug => {
+            let slug = title.map(ids::slugify).unwrap_or_default();
+            let slug = if slug.is_empty() {
+                "untitled".to_string()
+            } else {
+                slug
+            };
+            let taken = library_notes(app, dir)?
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+            ids::unique_slug(&slug, &taken)
+        }
+        ids::IdStrategy::Sequential => {
+            // The counter lives in the library so it survives reinstalls and
+            // moves with the notes.
+            let mut meta = library::load_meta(dir)?;
+            meta.last_sequence += 1;
+            library::save_meta(dir, &meta)?;
+            format!("{:05}", meta.last_sequence)
+        }
+    };
+    Ok(sanitize_id(&id))
+}
+
+fn note_record(
+    id: String,
+    path: &Path,
+    views: &views::Views,
+    storage: metadata::MetadataStorage,
+) -> Result<NoteRecord, String> {
+    let content = read_note_content(path)?;
+    Ok(NoteRecord {
+        last_viewed_ms: views.get(&id).copied(),
+        id,
+        path: path.to_string_lossy().to_string(),
+        icon: frontmatter::field(&content, "icon"),
+        expires_ms: frontmatter::field(&content, "expires_ms").and_then(|v| v.parse().ok()),
+        metadata: metadata::read(storage, path, &content)?,
+        content,
+    })
+}
+
+#[tauri::command]
+fn list_notes(app: tauri::AppHandle) -> Result<Vec<NoteRecord>, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+
+    let views = views::load(&app)?;
+    let files = library_notes(&app, &dir)?;
+    let mut notes = cache::records(&app, files, &views)?;
 
     // Deterministic order: newest-looking first (assuming your ids are note_<ms>)
     notes.sort_by(|a, b| b.id.cmp(&a.id));
@@ -124,18 +347,30 @@ fn list_notes(app: tauri::AppHandle) -> Result<Vec<NoteRecord>, String> {
 }
 
 #[tauri::command]
-fn create_note(app: tauri::AppHandle) -> Result<CreateNoteResponse, String> {
+fn create_note(app: tauri::AppHandle, title: Option<String>) -> Result<CreateNoteResponse, String> {
     let dir = notes_dir(&app)?;
     fs::create_dir_all(&dir).map_err(|e| format!("Failed to create notes dir: {e}"))?;
 
-    let id = sanitize_id(&generate_id());
+    let title = title.as_deref().map(str::trim).filter(|t| !t.is_empty());
+    if let Some(title) = title {
+        properties::ensure_unique_title(&app, &dir, title, None)?;
+    }
+    let id = generate_id(&app, &dir, title)?;
+    ids::ensure_not_reserved(&id)?;
     let path = note_path(&dir, &id);
 
-    // Default content (empty note). You can change this to include a title/frontmatter.
-    let content = String::new();
+    // Empty note, or just a frontmatter title when one was given and titles
+    // live in the frontmatter.
+    let storage = metadata::active(&app)?;
+    let content = match title {
+        Some(title) if storage == metadata::MetadataStorage::Frontmatter => {
+            frontmatter::set_field("", "title", Some(title))
+        }
+        _ => String::new(),
+    };
+    ensure_disk_space(&app, &dir, content.len())?;
 
     // Create exclusively; if collision (very unlikely), try a few more times.
-    // We avoid adding rand/uuid crates to keep it minimal.
     const MAX_TRIES: usize = 5;
     let mut attempt = 0usize;
     let final_path = loop {
@@ -167,12 +402,19 @@ fn create_note(app: tauri::AppHandle) -> Result<CreateNoteResponse, String> {
         }
     };
 
+    let id = final_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(&id)
+        .to_string();
+    if let Some(title) = title.filter(|_| storage == metadata::MetadataStorage::Sidecar) {
+        metadata::set_field(storage, &final_path, "", "title", Some(title))?;
+    }
+    links::invalidate(&app);
+    git::autocommit(&app, &final_path, &format!("Create {id}"));
+
     Ok(CreateNoteResponse {
-        id: final_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or(&id)
-            .to_string(),
+        id,
         path: final_path.to_string_lossy().to_string(),
         content,
     })
@@ -184,9 +426,22 @@ fn update_note(app: tauri::AppHandle, req: UpdateNoteRequest) -> Result<(), Stri
     fs::create_dir_all(&dir).map_err(|e| format!("Failed to create notes dir: {e}"))?;
 
     let id = sanitize_id(&req.id);
-    let path = note_path(&dir, &id);
+    let path = locate_note(&dir, &id);
+    ensure_disk_space(&app, &dir, req.content.len())?;
+
+    // Keep the version being replaced; a save isn't refused over history.
+    if let Ok(previous) = fs::read(&path) {
+        if previous != req.content.as_bytes() {
+            if let Err(e) = history::record(&app, &dir, &id, &previous) {
+                eprintln!("Failed to record history for {id}: {e}");
+            }
+        }
+    }
 
     fs::write(&path, req.content).map_err(|e| format!("Failed to write note file: {e}"))?;
+    cache::invalidate(&app, &id);
+    links::invalidate(&app);
+    git::autocommit(&app, &path, &format!("Update {id}"));
     Ok(())
 }
 
@@ -196,29 +451,198 @@ fn delete_note(app: tauri::AppHandle, req: DeleteNoteRequest) -> Result<(), Stri
     fs::create_dir_all(&dir).map_err(|e| format!("Failed to create notes dir: {e}"))?;
 
     let id = sanitize_id(&req.id);
-    let path = note_path(&dir, &id);
+    let path = locate_note(&dir, &id);
 
     match fs::remove_file(&path) {
-        Ok(()) => Ok(()),
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
-        Err(e) => Err(format!("Failed to delete note file: {e}")),
+        Ok(()) => trash::log_deleted(&dir, &id),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("Failed to delete note file: {e}")),
+    }
+    metadata::remove(&path)?;
+    cache::invalidate(&app, &id);
+    links::invalidate(&app);
+    git::autocommit(&app, &path, &format!("Delete {id}"));
+    Ok(())
+}
+
+/// Permanently deletes several notes at once. Returns the number removed;
+/// ids without a note are ignored.
+#[tauri::command]
+fn delete_notes(app: tauri::AppHandle, ids: Vec<String>, confirmed: bool) -> Result<usize, String> {
+    confirm::require_confirmation(&app, "delete_notes", confirmed)?;
+    let dir = ensure_notes_dir(&app)?;
+
+    let mut deleted = 0usize;
+    for id in ids {
+        let id = sanitize_id(&id);
+        let path = locate_note(&dir, &id);
+        match fs::remove_file(&path) {
+            Ok(()) => deleted += 1,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("Failed to delete note file ({id}): {e}")),
+        }
+        metadata::remove(&path)?;
+        trash::log_deleted(&dir, &id);
+        git::autocommit(&app, &path, &format!("Delete {id}"));
     }
+    cache::clear(&app);
+    links::invalidate(&app);
+    Ok(deleted)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn main() {
     tauri::Builder::default()
         .plugin(notification_init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .manage(links::LinkCache::default())
+        .manage(watcher::WatcherState::default())
+        .manage(watcher::Subscriptions::default())
+        .manage(watcher::WatcherPause::default())
+        .manage(pin::LockState::default())
+        .manage(backup::BackupLock::default())
+        .manage(cache::NoteCache::default())
         .invoke_handler(tauri::generate_handler![
             create_note,
             update_note,
             delete_note,
-            list_notes
+            list_notes,
+            cleanup::normalize_line_endings,
+            cleanup::normalize_all,
+            cleanup::trim_whitespace,
+            cleanup::trim_whitespace_all,
+            links::delete_impact,
+            properties::set_icon,
+            export::export_tag_folder,
+            settings::get_settings,
+            settings::update_settings,
+            git::git_log,
+            search::search_in_note,
+            library::library_version,
+            export::copy_note_plaintext,
+            render::render_markdown,
+            links::link_matrix,
+            links::orphan_notes,
+            links::read_note_rendered,
+            export::export_library_json,
+            import::import_library_json,
+            ids::slugify_ids,
+            ids::audit_ids,
+            ids::fix_ids,
+            watcher::subscribe_note,
+            watcher::unsubscribe_note,
+            stats::activity_heatmap,
+            relocate::relocate_notes_dir,
+            tasks::task_progress,
+            tasks::task_progress_all,
+            tasks::toggle_checkbox,
+            properties::set_expiry,
+            ids::short_id,
+            ids::resolve_short_id,
+            import::import_csv,
+            pin::set_pin,
+            pin::verify_pin,
+            pin::lock,
+            diff::diff_against_disk,
+            checkpoints::create_checkpoint,
+            checkpoints::list_checkpoints,
+            checkpoints::restore_checkpoint,
+            properties::update_frontmatter,
+            tags::tag_cooccurrence,
+            maintenance::rebuild_all,
+            read::tail_note,
+            schema::validate_schema,
+            schema::apply_schema_defaults,
+            stats::note_extremes,
+            properties::batch_retitle,
+            export::export_linked_bundle,
+            shell::add_to_quick_access,
+            properties::auto_color,
+            properties::auto_color_all,
+            backup::backup_now,
+            backup::unbacked_changes,
+            read::read_note_bytes,
+            read::write_note_bytes,
+            tags::set_tag_order,
+            tags::list_notes_by_tag,
+            similarity::near_duplicates,
+            links::notes_by_inbound_links,
+            trash::trash_stale,
+            index::generate_index,
+            tags::list_all_tags,
+            read::extract_section,
+            stats::writing_streak,
+            stats::note_edit_count,
+            enex::import_enex,
+            blocks::note_blocks,
+            blocks::move_block,
+            scroll::set_scroll_position,
+            scroll::get_scroll_position,
+            maintenance::vacuum,
+            links::audit_link_targets,
+            search::search_notes,
+            saved_searches::run_saved_searches,
+            git::git_status,
+            git::git_revert_note,
+            feed::generate_feed,
+            delete_notes,
+            trash::empty_trash,
+            backup::restore_backup,
+            cleanup::note_encoding_info,
+            properties::apply_frontmatter_template,
+            tree::notes_tree,
+            views::record_view,
+            views::stale_but_read,
+            export::export_site,
+            tags::promote_hashtags,
+            links::link_cycles,
+            backup::diff_backups,
+            watcher::pause_watcher,
+            watcher::resume_watcher,
+            tags::suggest_tags,
+            tags::tag_activity,
+            stats::long_notes,
+            permalink::note_permalink,
+            permalink::permalinks,
+            history::restore_version,
+            history::compress_history,
+            lint::render_errors,
+            docx::export_note_docx,
+            stats::creation_rate,
+            trash::purge_note,
+            links::attachment_references,
+            attachments::dedup_attachments,
+            read::note_body,
+            read::note_frontmatter_raw,
+            checkpoints::snapshot_frontmatter,
+            checkpoints::restore_frontmatter,
+            links::link_health,
+            cleanup::regex_transform,
+            history::export_history_timeline,
+            tree::duplicate_ids,
+            stats::word_frequency,
+            backup::next_backup_time,
+            attachments::resolve_attachment,
+            links::reachable_notes,
+            layout::set_storage_layout,
+            layout::move_note,
+            stats::daily_digest,
+            cache::clear_cache,
+            metadata::set_metadata_storage,
+            views::frequently_accessed
         ])
         .setup(|app| {
             // Ensure notes directory exists at startup.
             let dir = notes_dir(app.handle())?;
             fs::create_dir_all(&dir).map_err(|e| tauri::Error::Io(e))?;
+            library::migrate(&dir)?;
+            pin::init(app.handle())?;
+            if let Err(e) = watcher::start(app.handle(), &dir) {
+                eprintln!("{e}");
+            }
+            archive::start_expiry_task(app.handle());
+            saved_searches::start_saved_search_task(app.handle());
+            backup::start_auto_backup_task(app.handle());
             Ok(())
         })
         .run(tauri::generate_context!())
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, path::Path};
+
+use crate::{cache, ensure_notes_dir, library, library_notes, links, scroll, tags, views, watcher};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RebuildReport {
+    /// Notes found on disk.
+    notes: usize,
+    /// What was regenerated, one entry per cache/index.
+    rebuilt: Vec<String>,
+    /// Entries dropped because the note they point at no longer exists.
+    dropped: Vec<String>,
+}
+
+/// Removes sidecar and in-memory entries for notes that are gone. Returns a
+/// `kind: entry` line for each one dropped.
+pub fn drop_stale(
+    app: &tauri::AppHandle,
+    dir: &Path,
+    ids: &HashSet<String>,
+) -> Result<Vec<String>, String> {
+    let mut dropped = Vec::new();
+    for entry in tags::retain_tag_order(dir, ids)? {
+        dropped.push(format!("tag order: {entry}"));
+    }
+    for id in scroll::retain_positions(app, ids)? {
+        dropped.push(format!("scroll position: {id}"));
+    }
+    for id in views::retain_views(app, ids)? {
+        dropped.push(format!("view time: {id}"));
+    }
+    for id in watcher::retain_subscriptions(app, ids) {
+        dropped.push(format!("subscription: {id}"));
+    }
+    Ok(dropped)
+}
+
+fn note_ids(app: &tauri::AppHandle, dir: &Path) -> Result<HashSet<String>, String> {
+    Ok(library_notes(app, dir)?
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect())
+}
+
+/// Regenerates every cache and index from the note files themselves, for when
+/// the folder was changed behind the app's back.
+#[tauri::command]
+pub fn rebuild_all(app: tauri::AppHandle) -> Result<RebuildReport, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let ids = note_ids(&app, &dir)?;
+    let mut report = RebuildReport {
+        notes: ids.len(),
+        ..RebuildReport::default()
+    };
+
+    library::migrate(&dir)?;
+    report.rebuilt.push(format!(
+        "library metadata (schema v{})",
+        library::load_meta(&dir)?.schema_version
+    ));
+
+    cache::clear(&app);
+    links::invalidate(&app);
+    let matrix = links::matrix(&app)?;
+    report
+        .rebuilt
+        .push(format!("link matrix ({} notes)", matrix.len()));
+
+    report.dropped = drop_stale(&app, &dir, &ids)?;
+    Ok(report)
+}
+
+/// Cleans up leftovers of deleted notes without rebuilding anything.
+#[tauri::command]
+pub fn vacuum(app: tauri::AppHandle) -> Result<Vec<String>, String> {
+    let dir = ensure_notes_dir(&app)?;
+    drop_stale(&app, &dir, &note_ids(&app, &dir)?)
+}
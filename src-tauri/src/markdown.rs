@@ -0,0 +1,40 @@
+// Small line-level helpers shared by the commands that look inside note bodies.
+// None of this is a real markdown parser; it only recognises enough structure
+// to avoid touching fenced code.
+
+/// Whether `line` opens or closes a fenced code block (``` or ~~~).
+pub fn is_fence(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+}
+
+/// The lines of `content` paired with whether each one sits inside a fenced
+/// code block. Fence delimiter lines themselves count as inside.
+pub fn lines_with_fences(content: &str) -> Vec<(&str, bool)> {
+    let mut in_fence = false;
+    content
+        .lines()
+        .map(|line| {
+            if is_fence(line) {
+                in_fence = !in_fence;
+                (line, true)
+            } else {
+                (line, in_fence)
+            }
+        })
+        .collect()
+}
+
+/// Level and text of an ATX heading line (`# Title` through `###### Title`).
+pub fn heading(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim_start();
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    Some((level, rest.trim().trim_end_matches('#').trim_end()))
+}
@@ -0,0 +1,281 @@
+// Where a note's title, tags, pinned flag and color live: in its frontmatter
+// (the default), or with the `Sidecar` storage in `<id>.meta.json` next to
+// the note so the markdown holds only content. Other frontmatter fields stay
+// in the note either way.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cache, ensure_notes_dir, frontmatter, library_notes, read_note_content, settings, sidecar,
+    transaction::Transaction, watcher,
+};
+
+const META_KEYS: [&str; 4] = ["title", "tags", "pinned", "color"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MetadataStorage {
+    #[default]
+    Frontmatter,
+    Sidecar,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoteMeta {
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+    pub pinned: bool,
+    pub color: Option<String>,
+}
+
+/// `<id>.meta.json` next to the note at `note`.
+pub fn sidecar_path(note: &Path) -> PathBuf {
+    note.with_extension("meta.json")
+}
+
+/// The sidecar metadata of the note at `note`, `None` when it has none.
+pub fn load(note: &Path) -> Result<Option<NoteMeta>, String> {
+    let path = sidecar_path(note);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    sidecar::load(&path).map(Some)
+}
+
+/// Deletes the sidecar of the note at `note`, if it has one.
+pub fn remove(note: &Path) -> Result<(), String> {
+    match fs::remove_file(sidecar_path(note)) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to remove metadata sidecar: {e}")),
+    }
+}
+
+fn from_frontmatter(content: &str) -> NoteMeta {
+    NoteMeta {
+        title: frontmatter::field(content, "title"),
+        tags: frontmatter::list(content, "tags"),
+        pinned: frontmatter::field(content, "pinned").is_some_and(|v| v == "true"),
+        color: frontmatter::field(content, "color"),
+    }
+}
+
+/// `content` with the fields `meta` has set written into its frontmatter.
+fn lay_over(content: &str, meta: &NoteMeta) -> String {
+    let mut updated = content.to_string();
+    if let Some(title) = &meta.title {
+        updated = frontmatter::set_field(&updated, "title", Some(title));
+    }
+    if !meta.tags.is_empty() {
+        updated = frontmatter::set_raw(
+            &updated,
+            "tags",
+            Some(&frontmatter::inline_list(&meta.tags)),
+        );
+    }
+    if meta.pinned {
+        updated = frontmatter::set_raw(&updated, "pinned", Some("true"));
+    }
+    if let Some(color) = &meta.color {
+        updated = frontmatter::set_field(&updated, "color", Some(color));
+    }
+    updated
+}
+
+/// `content` without the frontmatter fields `keys`, and without the block
+/// when nothing else is left in it.
+fn strip(content: &str, keys: &[&str]) -> String {
+    let mut updated = content.to_string();
+    for key in keys {
+        updated = frontmatter::set_raw(&updated, key, None);
+    }
+    if frontmatter::split(&updated)
+        .0
+        .is_some_and(|fm| fm.trim().is_empty())
+    {
+        updated = frontmatter::replace_block(&updated, None);
+    }
+    updated
+}
+
+pub fn active(app: &tauri::AppHandle) -> Result<MetadataStorage, String> {
+    Ok(settings::load(app)?.metadata_storage)
+}
+
+/// The note at `note` with `content` as readers of titles, tags, pinned and
+/// color should see it: with `Sidecar` storage, the sidecar's fields laid
+/// over the frontmatter. Only for reading; never write it back to the note.
+pub fn view(storage: MetadataStorage, note: &Path, content: String) -> Result<String, String> {
+    if storage == MetadataStorage::Frontmatter {
+        return Ok(content);
+    }
+    Ok(match load(note)? {
+        Some(meta) => lay_over(&content, &meta),
+        None => content,
+    })
+}
+
+/// Reads the note at `note` through [`view`].
+pub fn read_view(storage: MetadataStorage, note: &Path) -> Result<String, String> {
+    view(storage, note, read_note_content(note)?)
+}
+
+/// Title, tags, pinned and color of the note at `note` with `content`, from
+/// the active storage.
+pub fn read(storage: MetadataStorage, note: &Path, content: &str) -> Result<NoteMeta, String> {
+    Ok(from_frontmatter(&view(storage, note, content.to_string())?))
+}
+
+/// Sets the raw frontmatter value of `key` (or removes it with `None`) for
+/// the note at `note`. With `Sidecar` storage, title, tags, pinned and color
+/// go to the sidecar, saved right away, and any stale copy is stripped from
+/// the frontmatter. Returns the content to write to the note, which the
+/// caller does.
+pub fn set_raw(
+    storage: MetadataStorage,
+    note: &Path,
+    content: &str,
+    key: &str,
+    raw: Option<&str>,
+) -> Result<String, String> {
+    if storage == MetadataStorage::Frontmatter || !META_KEYS.contains(&key) {
+        return Ok(frontmatter::set_raw(content, key, raw));
+    }
+
+    // Parsed the way a frontmatter value would be.
+    let parsed = from_frontmatter(&frontmatter::set_raw("", key, raw));
+    let mut meta = load(note)?.unwrap_or_default();
+    match key {
+        "title" => meta.title = parsed.title,
+        "tags" => meta.tags = parsed.tags,
+        "pinned" => meta.pinned = parsed.pinned,
+        _ => meta.color = parsed.color,
+    }
+    sidecar::save(&sidecar_path(note), &meta)?;
+    Ok(strip(content, &[key]))
+}
+
+/// [`set_raw`] for a plain scalar value, quoted as needed.
+pub fn set_field(
+    storage: MetadataStorage,
+    note: &Path,
+    content: &str,
+    key: &str,
+    value: Option<&str>,
+) -> Result<String, String> {
+    set_raw(
+        storage,
+        note,
+        content,
+        key,
+        value.map(frontmatter::scalar).as_deref(),
+    )
+}
+
+/// Moves the sidecar of the note at `from`, if any, to go with it to `to`.
+pub fn move_with(from: &Path, to: &Path) -> Result<(), String> {
+    let source = sidecar_path(from);
+    if !source.is_file() {
+        return Ok(());
+    }
+    fs::rename(&source, sidecar_path(to))
+        .map_err(|e| format!("Failed to move metadata sidecar: {e}"))
+}
+
+/// Stages moving the sidecar of the note at `from` (if it has one) to go
+/// with the note's new file `to`. A sidecar already at `to` is left alone.
+pub fn stage_move(txn: &mut Transaction, from: &Path, to: &Path) {
+    let (source, target) = (sidecar_path(from), sidecar_path(to));
+    if source.is_file() && !target.exists() {
+        txn.rename(&source, &target);
+    }
+}
+
+/// Moves the metadata fields of one note out of its frontmatter into a
+/// sidecar. Returns whether anything moved.
+fn to_sidecar(note: &Path) -> Result<bool, String> {
+    let content = read_note_content(note)?;
+    // Fields already in a sidecar are kept unless the frontmatter has them too.
+    let mut meta = load(note)?.unwrap_or_default();
+    let found = from_frontmatter(&content);
+    if found.title.is_some() {
+        meta.title = found.title;
+    }
+    if !found.tags.is_empty() {
+        meta.tags = found.tags;
+    }
+    if frontmatter::field(&content, "pinned").is_some() {
+        meta.pinned = found.pinned;
+    }
+    if found.color.is_some() {
+        meta.color = found.color;
+    }
+
+    let updated = strip(&content, &META_KEYS);
+    if updated == content {
+        return Ok(false);
+    }
+
+    // Sidecar first: if writing the note fails, the fields exist in both.
+    sidecar::save(&sidecar_path(note), &meta)?;
+    fs::write(note, updated).map_err(|e| format!("Failed to write note file: {e}"))?;
+    Ok(true)
+}
+
+/// Files the metadata of a note just written with it in the frontmatter (an
+/// import, say) where the active storage keeps it. A sidecar left over from
+/// a note the write replaced is dropped first, so it can't shadow the new
+/// content.
+pub fn adopt(storage: MetadataStorage, note: &Path) -> Result<(), String> {
+    remove(note)?;
+    if storage == MetadataStorage::Sidecar {
+        to_sidecar(note)?;
+    }
+    Ok(())
+}
+
+/// Writes a note's sidecar metadata back into its frontmatter and removes
+/// the sidecar. Returns whether there was one.
+fn to_frontmatter(note: &Path) -> Result<bool, String> {
+    let Some(meta) = load(note)? else {
+        return Ok(false);
+    };
+    let updated = lay_over(&read_note_content(note)?, &meta);
+    fs::write(note, updated).map_err(|e| format!("Failed to write note file: {e}"))?;
+    remove(note)?;
+    Ok(true)
+}
+
+/// Switches where title, tags, pinned and color are kept, converting every
+/// note to match. Running it again for the current storage finishes a
+/// conversion that stopped partway. Returns the number of notes converted.
+#[tauri::command]
+pub fn set_metadata_storage(
+    app: tauri::AppHandle,
+    storage: MetadataStorage,
+) -> Result<usize, String> {
+    let dir = ensure_notes_dir(&app)?;
+
+    let _paused = watcher::pause(&app);
+    let mut converted = 0usize;
+    for (_, note) in library_notes(&app, &dir)? {
+        let changed = match storage {
+            MetadataStorage::Frontmatter => to_frontmatter(&note)?,
+            MetadataStorage::Sidecar => to_sidecar(&note)?,
+        };
+        if changed {
+            converted += 1;
+        }
+    }
+
+    let mut current = settings::load(&app)?;
+    current.metadata_storage = storage;
+    settings::save(&app, &current)?;
+    // Cached records were built from the old storage.
+    cache::clear_cache(app)?;
+    Ok(converted)
+}
@@ -0,0 +1,97 @@
+// Stable public URLs for published notes, shared by the site export and the
+// feed so every output links a note the same way.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use crate::{
+    ensure_notes_dir, existing_note_path, frontmatter, ids, library_notes, metadata, note_title,
+    sanitize_id, settings,
+};
+
+pub fn is_published(content: &str) -> bool {
+    frontmatter::field(content, "publish").as_deref() == Some("true")
+}
+
+/// URL slug of each note in `notes` (id, content): the slugified title, or the
+/// id for untitled notes. Clashes are numbered in id order, so a note's slug
+/// only changes when its title does or an earlier id takes it.
+pub fn slugs(notes: &[(String, String)]) -> HashMap<String, String> {
+    let mut sorted: Vec<&(String, String)> = notes.iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut taken = HashSet::new();
+    let mut slugs = HashMap::new();
+    for (id, content) in sorted {
+        let title = note_title(id, content);
+        let slug = match ids::slugify(&title) {
+            slug if slug.is_empty() || title == *id => id.clone(),
+            slug => slug,
+        };
+        let slug = ids::unique_slug(&slug, &taken);
+        taken.insert(slug.clone());
+        slugs.insert(id.clone(), slug);
+    }
+    slugs
+}
+
+/// The configured base URL without a trailing slash, if any.
+pub fn base_url(app: &tauri::AppHandle) -> Result<Option<String>, String> {
+    Ok(settings::load(app)?
+        .publish_base_url
+        .map(|base| base.trim().trim_end_matches('/').to_string())
+        .filter(|base| !base.is_empty()))
+}
+
+fn require_base_url(app: &tauri::AppHandle) -> Result<String, String> {
+    base_url(app)?.ok_or_else(|| "No publish base URL configured".to_string())
+}
+
+/// Every published note as (id, content).
+pub fn published_notes(
+    app: &tauri::AppHandle,
+    dir: &Path,
+) -> Result<Vec<(String, String)>, String> {
+    let storage = metadata::active(app)?;
+    let mut notes = Vec::new();
+    for (id, path) in library_notes(app, dir)? {
+        let content = metadata::read_view(storage, &path)?;
+        if is_published(&content) {
+            notes.push((id, content));
+        }
+    }
+    Ok(notes)
+}
+
+/// `<base>/<slug>` for the note. Slugs are numbered among the published
+/// notes, so an unpublished note gets the link it would have once published.
+#[tauri::command]
+pub fn note_permalink(app: tauri::AppHandle, id: String) -> Result<String, String> {
+    let base = require_base_url(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    let id = sanitize_id(&id);
+
+    let mut notes = published_notes(&app, &dir)?;
+    if !notes.iter().any(|(other, _)| *other == id) {
+        let content = metadata::read_view(metadata::active(&app)?, &path)?;
+        notes.push((id.clone(), content));
+    }
+    Ok(format!("{base}/{}", slugs(&notes)[&id]))
+}
+
+/// Permalinks of all published notes as (id, url), by id.
+#[tauri::command]
+pub fn permalinks(app: tauri::AppHandle) -> Result<Vec<(String, String)>, String> {
+    let base = require_base_url(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+
+    let mut links: Vec<(String, String)> = slugs(&published_notes(&app, &dir)?)
+        .into_iter()
+        .map(|(id, slug)| (id, format!("{base}/{slug}")))
+        .collect();
+    links.sort();
+    Ok(links)
+}
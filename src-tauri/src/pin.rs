@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use tauri::Manager;
+
+use crate::sidecar;
+
+const PIN_FILE: &str = "pin.json";
+const MIN_PIN_LEN: usize = 4;
+// Stretching makes brute-forcing a copied pin.json slower; it is still only
+// meant to stop casual snooping, not to replace encryption.
+const HASH_ROUNDS: u32 = 100_000;
+
+/// Whether sensitive commands are currently refused.
+#[derive(Default)]
+pub struct LockState(AtomicBool);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoredPin {
+    salt: String,
+    hash: String,
+}
+
+fn pin_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let base = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {e}"))?;
+    Ok(base.join(PIN_FILE))
+}
+
+fn hash_pin(salt: &str, pin: &str) -> String {
+    let mut digest = Sha256::new()
+        .chain_update(salt.as_bytes())
+        .chain_update(pin.as_bytes())
+        .finalize();
+    for _ in 1..HASH_ROUNDS {
+        digest = Sha256::new()
+            .chain_update(salt.as_bytes())
+            .chain_update(digest)
+            .finalize();
+    }
+    digest.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn stored_pin(app: &tauri::AppHandle) -> Result<Option<StoredPin>, String> {
+    let stored: StoredPin = sidecar::load(&pin_path(app)?)?;
+    Ok((!stored.hash.is_empty()).then_some(stored))
+}
+
+fn set_locked(app: &tauri::AppHandle, locked: bool) {
+    app.state::<LockState>().0.store(locked, Ordering::SeqCst);
+}
+
+/// Starts the app locked when a PIN has been set.
+pub fn init(app: &tauri::AppHandle) -> Result<(), String> {
+    set_locked(app, stored_pin(app)?.is_some());
+    Ok(())
+}
+
+pub fn is_locked(app: &tauri::AppHandle) -> bool {
+    app.state::<LockState>().0.load(Ordering::SeqCst)
+}
+
+/// Gate for every command that returns note content, titles or anything
+/// derived from them. Writes (`update_note`, `write_note_bytes`, ...) are
+/// not gated: they reveal nothing, and refusing an autosave that races the
+/// lock would lose what was just typed.
+pub fn ensure_unlocked(app: &tauri::AppHandle) -> Result<(), String> {
+    if is_locked(app) {
+        return Err("App is locked".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_pin(app: tauri::AppHandle, pin: String) -> Result<(), String> {
+    // Changing an existing PIN requires having unlocked with the old one.
+    ensure_unlocked(&app)?;
+    if pin.chars().count() < MIN_PIN_LEN {
+        return Err(format!("PIN must be at least {MIN_PIN_LEN} characters"));
+    }
+
+    let salt = uuid::Uuid::new_v4().simple().to_string();
+    let stored = StoredPin {
+        hash: hash_pin(&salt, &pin),
+        salt,
+    };
+    sidecar::save(&pin_path(&app)?, &stored)
+}
+
+/// Checks `pin` and unlocks the app when it matches.
+#[tauri::command]
+pub fn verify_pin(app: tauri::AppHandle, pin: String) -> Result<bool, String> {
+    let Some(stored) = stored_pin(&app)? else {
+        return Err("No PIN set".to_string());
+    };
+
+    let candidate = hash_pin(&stored.salt, &pin);
+    // Compare without bailing out at the first differing byte.
+    let matches = candidate.len() == stored.hash.len()
+        && candidate
+            .bytes()
+            .zip(stored.hash.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0;
+    if matches {
+        set_locked(&app, false);
+    }
+    Ok(matches)
+}
+
+#[tauri::command]
+pub fn lock(app: tauri::AppHandle) -> Result<(), String> {
+    if stored_pin(&app)?.is_none() {
+        return Err("No PIN set".to_string());
+    }
+    set_locked(&app, true);
+    Ok(())
+}
@@ -0,0 +1,365 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{
+    cache, ensure_notes_dir, existing_note_path, frontmatter, hash, ids, library_notes, links,
+    metadata::{self, MetadataStorage},
+    note_title, read_note_content, sanitize_id, settings, tags, watcher,
+};
+
+// Long enough for multi-codepoint emoji (ZWJ sequences, flags, skin tones)
+// and short labels, short enough to keep it an icon rather than a title.
+const MAX_ICON_CHARS: usize = 16;
+
+// Grey for notes without tags, so they read as "uncategorised".
+const NEUTRAL_COLOR: &str = "hsl(0, 0%, 60%)";
+
+const TITLE_PLACEHOLDER: &str = "{{title}}";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApplyResult {
+    id: String,
+    /// Template keys the note didn't have.
+    added: Vec<String>,
+    /// Existing keys replaced by the template value (only with `overwrite`).
+    changed: Vec<String>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RetitleResult {
+    id: String,
+    /// The new title, when the note was retitled.
+    title: Option<String>,
+    error: Option<String>,
+}
+
+fn title_key(title: &str) -> String {
+    title.trim().to_lowercase()
+}
+
+/// With `enforce_unique_titles` on, rejects `title` if another note (any but
+/// `own_id`) already uses it. Comparison ignores case and surrounding spaces.
+pub fn ensure_unique_title(
+    app: &tauri::AppHandle,
+    dir: &Path,
+    title: &str,
+    own_id: Option<&str>,
+) -> Result<(), String> {
+    if !settings::load(app)?.enforce_unique_titles {
+        return Ok(());
+    }
+
+    let wanted = title_key(title);
+    let storage = metadata::active(app)?;
+    for (id, path) in library_notes(app, dir)? {
+        if Some(id.as_str()) == own_id {
+            continue;
+        }
+        let existing = note_title(&id, &metadata::read_view(storage, &path)?);
+        // Untitled notes fall back to their id, which isn't a title to clash with.
+        if existing != id && title_key(&existing) == wanted {
+            return Err(format!("Duplicate title: already used by {id}"));
+        }
+    }
+    Ok(())
+}
+
+fn validate_field(key: &str, value: Option<&str>) -> Result<(), String> {
+    if key.is_empty() || key.contains([':', '\n', '\r']) || key != key.trim() {
+        return Err(format!("Invalid frontmatter key: {key:?}"));
+    }
+    if value.is_some_and(|v| v.contains(['\n', '\r'])) {
+        return Err(format!("Frontmatter value for {key} must be a single line"));
+    }
+    Ok(())
+}
+
+fn validate_icon(icon: &str) -> Result<&str, String> {
+    let icon = icon.trim();
+    if icon.is_empty() {
+        return Err("Icon must not be empty".to_string());
+    }
+    if icon.chars().count() > MAX_ICON_CHARS {
+        return Err(format!("Icon must be at most {MAX_ICON_CHARS} characters"));
+    }
+    if icon.chars().any(char::is_control) {
+        return Err("Icon must not contain control characters".to_string());
+    }
+    Ok(icon)
+}
+
+#[tauri::command]
+pub fn set_icon(app: tauri::AppHandle, id: String, icon: Option<String>) -> Result<(), String> {
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+
+    let icon = icon.as_deref().map(validate_icon).transpose()?;
+    let content = read_note_content(&path)?;
+    let updated = metadata::set_field(metadata::active(&app)?, &path, &content, "icon", icon)?;
+    if updated == content {
+        return Ok(());
+    }
+
+    fs::write(&path, updated).map_err(|e| format!("Failed to write note file: {e}"))?;
+    cache::invalidate(&app, &sanitize_id(&id));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_expiry(app: tauri::AppHandle, id: String, at_ms: Option<u64>) -> Result<(), String> {
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+
+    let content = read_note_content(&path)?;
+    let at_ms = at_ms.map(|ms| ms.to_string());
+    let updated = frontmatter::set_field(&content, "expires_ms", at_ms.as_deref());
+    if updated == content {
+        return Ok(());
+    }
+
+    fs::write(&path, updated).map_err(|e| format!("Failed to write note file: {e}"))?;
+    cache::invalidate(&app, &sanitize_id(&id));
+    Ok(())
+}
+
+/// Sets (or with `None`, removes) top-level frontmatter fields, leaving the
+/// rest of the note untouched. With `sync_title_to_filename` on, a changed
+/// title also renames the note; the new id is returned then.
+#[tauri::command]
+pub fn update_frontmatter(
+    app: tauri::AppHandle,
+    id: String,
+    fields: HashMap<String, Option<String>>,
+) -> Result<Option<String>, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    for (key, value) in &fields {
+        validate_field(key, value.as_deref())?;
+    }
+    if let Some(Some(title)) = fields.get("title") {
+        ensure_unique_title(&app, &dir, title, Some(&sanitize_id(&id)))?;
+    }
+
+    let storage = metadata::active(&app)?;
+    let content = read_note_content(&path)?;
+    let old_title = frontmatter::field(&metadata::view(storage, &path, content.clone())?, "title");
+    let mut keys: Vec<&String> = fields.keys().collect();
+    keys.sort();
+    let mut updated = content.clone();
+    for key in keys {
+        updated = metadata::set_field(storage, &path, &updated, key, fields[key].as_deref())?;
+    }
+
+    let new_title = fields.get("title").cloned().flatten();
+    let title_changed = new_title.is_some() && new_title != old_title;
+    // With sidecar storage the fields may all have gone to the sidecar.
+    if updated == content && !title_changed {
+        return Ok(None);
+    }
+    if let Some(title) = new_title.filter(|_| title_changed) {
+        if settings::load(&app)?.sync_title_to_filename {
+            // One notes-changed event for the rename and link updates, which
+            // the frontend handles like any other; nothing calls back here.
+            let _paused = watcher::pause(&app);
+            let renamed = ids::rename_to_title(&app, &dir, &sanitize_id(&id), &title, &updated)?;
+            if renamed.is_some() {
+                cache::invalidate(&app, &sanitize_id(&id));
+                links::invalidate(&app);
+                return Ok(renamed);
+            }
+        }
+    }
+
+    fs::write(&path, updated).map_err(|e| format!("Failed to write note file: {e}"))?;
+    cache::invalidate(&app, &sanitize_id(&id));
+    Ok(None)
+}
+
+fn retitle(app: &tauri::AppHandle, dir: &Path, id: &str, pattern: &str) -> Result<String, String> {
+    let path = existing_note_path(dir, id)?;
+    let id = sanitize_id(id);
+    let storage = metadata::active(app)?;
+    let content = read_note_content(&path)?;
+
+    // Untitled notes start from the same title the list shows for them.
+    let current = note_title(&id, &metadata::view(storage, &path, content.clone())?);
+    let title = pattern.replace(TITLE_PLACEHOLDER, &current);
+    validate_field("title", Some(&title))?;
+    ensure_unique_title(app, dir, &title, Some(&id))?;
+
+    let updated = metadata::set_field(storage, &path, &content, "title", Some(&title))?;
+    if updated != content {
+        fs::write(&path, updated).map_err(|e| format!("Failed to write note file: {e}"))?;
+    }
+    Ok(title)
+}
+
+/// Rewrites the frontmatter title of each note from `pattern`, where
+/// `{{title}}` stands for the note's current title. One note failing doesn't
+/// stop the others.
+#[tauri::command]
+pub fn batch_retitle(
+    app: tauri::AppHandle,
+    ids: Vec<String>,
+    pattern: String,
+) -> Result<Vec<RetitleResult>, String> {
+    if !pattern.contains(TITLE_PLACEHOLDER) {
+        return Err(format!("Pattern must contain {TITLE_PLACEHOLDER}"));
+    }
+    let dir = ensure_notes_dir(&app)?;
+
+    let results: Vec<_> = ids
+        .into_iter()
+        .map(|id| match retitle(&app, &dir, &id, &pattern) {
+            Ok(title) => RetitleResult {
+                id,
+                title: Some(title),
+                error: None,
+            },
+            Err(e) => RetitleResult {
+                id,
+                title: None,
+                error: Some(e),
+            },
+        })
+        .collect();
+    cache::clear(&app);
+    Ok(results)
+}
+
+/// A stable color for the note's first tag: the same tag always maps to the
+/// same hue, on every platform.
+fn tag_color(content: &str) -> String {
+    match tags::note_tags(content).first() {
+        Some(tag) => format!("hsl({}, 65%, 50%)", hash::fnv1a64(tag.as_bytes()) % 360),
+        None => NEUTRAL_COLOR.to_string(),
+    }
+}
+
+/// Writes the derived `color` field to the note at `path`. Returns the color
+/// and whether the file changed.
+fn auto_color_file(storage: MetadataStorage, path: &Path) -> Result<(String, bool), String> {
+    let content = read_note_content(path)?;
+    let shown = metadata::view(storage, path, content.clone())?;
+    let color = tag_color(&shown);
+    if frontmatter::field(&shown, "color").as_deref() == Some(color.as_str()) {
+        return Ok((color, false));
+    }
+    let updated = metadata::set_field(storage, path, &content, "color", Some(&color))?;
+    if updated == content {
+        // Only the sidecar changed.
+        return Ok((color, true));
+    }
+
+    fs::write(path, updated).map_err(|e| format!("Failed to write note file: {e}"))?;
+    Ok((color, true))
+}
+
+#[tauri::command]
+pub fn auto_color(app: tauri::AppHandle, id: String) -> Result<String, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    let (color, changed) = auto_color_file(metadata::active(&app)?, &path)?;
+    if changed {
+        cache::invalidate(&app, &sanitize_id(&id));
+    }
+    Ok(color)
+}
+
+#[tauri::command]
+pub fn auto_color_all(app: tauri::AppHandle) -> Result<usize, String> {
+    let dir = ensure_notes_dir(&app)?;
+
+    let storage = metadata::active(&app)?;
+    let mut changed = 0usize;
+    for (_, path) in library_notes(&app, &dir)? {
+        if auto_color_file(storage, &path)?.1 {
+            changed += 1;
+        }
+    }
+    if changed > 0 {
+        cache::clear(&app);
+    }
+    Ok(changed)
+}
+
+/// Merges `template` fields into the note's frontmatter. Returns the keys
+/// added and changed.
+fn apply_template(
+    storage: MetadataStorage,
+    dir: &Path,
+    id: &str,
+    template: &[(String, String)],
+    overwrite: bool,
+) -> Result<(Vec<String>, Vec<String>), String> {
+    let path = existing_note_path(dir, id)?;
+    let content = read_note_content(&path)?;
+    let shown = metadata::view(storage, &path, content.clone())?;
+    let existing: HashMap<String, String> = frontmatter::split(&shown)
+        .0
+        .map(frontmatter::raw_entries)
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+
+    let mut updated = content.clone();
+    let (mut added, mut changed) = (Vec::new(), Vec::new());
+    for (key, value) in template {
+        match existing.get(key) {
+            None => added.push(key.clone()),
+            Some(current) if overwrite && current != value => changed.push(key.clone()),
+            Some(_) => continue,
+        }
+        updated = metadata::set_raw(storage, &path, &updated, key, Some(value))?;
+    }
+
+    if updated != content {
+        fs::write(&path, updated).map_err(|e| format!("Failed to write note file: {e}"))?;
+    }
+    Ok((added, changed))
+}
+
+/// Backfills frontmatter from `template` (a frontmatter block, with or
+/// without its `---` fences) into each note. Existing keys are kept unless
+/// `overwrite` is set; note bodies are never touched.
+#[tauri::command]
+pub fn apply_frontmatter_template(
+    app: tauri::AppHandle,
+    ids: Vec<String>,
+    template: String,
+    overwrite: bool,
+) -> Result<Vec<ApplyResult>, String> {
+    let block = match frontmatter::split(&template) {
+        (Some(fm), _) => fm,
+        (None, _) => template.as_str(),
+    };
+    let fields = frontmatter::raw_entries(block);
+    if fields.is_empty() {
+        return Err("Template has no frontmatter fields".to_string());
+    }
+    let dir = ensure_notes_dir(&app)?;
+    let storage = metadata::active(&app)?;
+
+    let results: Vec<_> = ids
+        .into_iter()
+        .map(
+            |id| match apply_template(storage, &dir, &id, &fields, overwrite) {
+                Ok((added, changed)) => ApplyResult {
+                    id,
+                    added,
+                    changed,
+                    error: None,
+                },
+                Err(e) => ApplyResult {
+                    id,
+                    added: Vec::new(),
+                    changed: Vec::new(),
+                    error: Some(e),
+                },
+            },
+        )
+        .collect();
+    cache::clear(&app);
+    Ok(results)
+}
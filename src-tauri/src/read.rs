@@ -0,0 +1,236 @@
+use std::{
+    fs,
+    io::{Read, Seek, SeekFrom},
+};
+
+use crate::{
+    cache, ensure_disk_space, ensure_notes_dir, existing_note_path, frontmatter, git, history, ids,
+    links, locate_note, markdown, pin, read_note_content, sanitize_id,
+};
+
+const TAIL_CHUNK: u64 = 8 * 1024;
+
+/// Byte offset where the last `lines` lines of `file` begin. Reads backwards in
+/// fixed-size chunks, so only the tail of the file is ever in memory.
+fn tail_start(file: &mut fs::File, len: u64, lines: usize) -> std::io::Result<u64> {
+    let mut pos = len;
+    let mut newlines = 0;
+    let mut buf = vec![0u8; TAIL_CHUNK as usize];
+    // A newline terminating the last line doesn't start another one.
+    let mut skip_final_newline = true;
+
+    while pos > 0 {
+        let read = TAIL_CHUNK.min(pos);
+        pos -= read;
+        file.seek(SeekFrom::Start(pos))?;
+        let chunk = &mut buf[..read as usize];
+        file.read_exact(chunk)?;
+
+        for (i, &byte) in chunk.iter().enumerate().rev() {
+            if byte != b'\n' {
+                skip_final_newline = false;
+                continue;
+            }
+            if skip_final_newline {
+                skip_final_newline = false;
+                continue;
+            }
+            newlines += 1;
+            if newlines == lines {
+                return Ok(pos + i as u64 + 1);
+            }
+        }
+    }
+    Ok(0)
+}
+
+#[tauri::command]
+pub fn tail_note(app: tauri::AppHandle, id: String, lines: usize) -> Result<String, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    if lines == 0 {
+        return Ok(String::new());
+    }
+
+    let mut file = fs::File::open(&path).map_err(|e| format!("Failed to open note: {e}"))?;
+    let len = file
+        .metadata()
+        .map_err(|e| format!("Failed to read note metadata: {e}"))?
+        .len();
+    let start =
+        tail_start(&mut file, len, lines).map_err(|e| format!("Failed to read note: {e}"))?;
+
+    file.seek(SeekFrom::Start(start))
+        .map_err(|e| format!("Failed to read note: {e}"))?;
+    let mut tail = Vec::with_capacity((len - start) as usize);
+    file.read_to_end(&mut tail)
+        .map_err(|e| format!("Failed to read note: {e}"))?;
+    // `start` always follows a newline (or is 0), so it is a char boundary.
+    String::from_utf8(tail).map_err(|e| format!("Note is not valid UTF-8: {e}"))
+}
+
+/// The note file exactly as stored, for content that isn't valid UTF-8.
+#[tauri::command]
+pub fn read_note_bytes(app: tauri::AppHandle, id: String) -> Result<Vec<u8>, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    fs::read(&path).map_err(|e| format!("Failed to read note file: {e}"))
+}
+
+/// Byte-for-byte counterpart of `update_note`.
+#[tauri::command]
+pub fn write_note_bytes(app: tauri::AppHandle, id: String, bytes: Vec<u8>) -> Result<(), String> {
+    let dir = ensure_notes_dir(&app)?;
+    let id = sanitize_id(&id);
+    let path = locate_note(&dir, &id);
+    if !path.exists() {
+        ids::ensure_not_reserved(&id)?;
+    }
+    ensure_disk_space(&app, &dir, bytes.len())?;
+
+    if let Ok(previous) = fs::read(&path) {
+        if previous != bytes {
+            if let Err(e) = history::record(&app, &dir, &id, &previous) {
+                eprintln!("Failed to record history for {id}: {e}");
+            }
+        }
+    }
+
+    fs::write(&path, bytes).map_err(|e| format!("Failed to write note file: {e}"))?;
+    cache::invalidate(&app, &id);
+    links::invalidate(&app);
+    git::autocommit(&app, &path, &format!("Update {id}"));
+    Ok(())
+}
+
+/// The section under the first heading whose text matches `heading`
+/// (case-insensitively), from the heading line up to the next heading of the
+/// same or a higher level. Headings inside code fences are ignored.
+pub fn section<'a>(content: &'a str, heading: &str) -> Option<&'a str> {
+    let wanted = heading.trim().trim_start_matches('#').trim().to_lowercase();
+    let body = frontmatter::body(content);
+
+    let mut in_fence = false;
+    let mut start: Option<(usize, usize)> = None;
+    let mut offset = 0;
+    for line in body.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        if markdown::is_fence(line) {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        let Some((level, text)) = markdown::heading(line) else {
+            continue;
+        };
+
+        match start {
+            Some((from, open_level)) if level <= open_level => {
+                return Some(&body[from..line_start]);
+            }
+            None if text.to_lowercase() == wanted => start = Some((line_start, level)),
+            _ => {}
+        }
+    }
+    start.map(|(from, _)| &body[from..])
+}
+
+#[tauri::command]
+pub fn extract_section(
+    app: tauri::AppHandle,
+    id: String,
+    heading: String,
+) -> Result<String, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    let content = read_note_content(&path)?;
+    section(&content, &heading)
+        .map(str::to_string)
+        .ok_or_else(|| format!("Heading not found: {}", heading.trim()))
+}
+
+/// The note without its leading frontmatter block; the whole note when it
+/// has none.
+#[tauri::command]
+pub fn note_body(app: tauri::AppHandle, id: String) -> Result<String, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    Ok(frontmatter::body(&read_note_content(&path)?).to_string())
+}
+
+/// The text between the frontmatter fences, exactly as written, or `None`
+/// when the note doesn't start with a frontmatter block. Together with
+/// [`note_body`] this covers the whole note apart from the fences.
+#[tauri::command]
+pub fn note_frontmatter_raw(app: tauri::AppHandle, id: String) -> Result<Option<String>, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    Ok(frontmatter::split(&read_note_content(&path)?)
+        .0
+        .map(str::to_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_file(name: &str, content: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("tail-{name}-{}", std::process::id()));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    /// The last `lines` lines of `content`, as `tail_note` returns them.
+    fn tail(name: &str, content: &str, lines: usize) -> String {
+        let path = temp_file(name, content.as_bytes());
+        let mut file = fs::File::open(&path).unwrap();
+        let start = tail_start(&mut file, content.len() as u64, lines).unwrap();
+        fs::remove_file(&path).unwrap();
+        content[start as usize..].to_string()
+    }
+
+    #[test]
+    fn tail_of_short_file() {
+        assert_eq!(tail("short", "one\ntwo\nthree\n", 2), "two\nthree\n");
+        assert_eq!(tail("short-one", "one\ntwo\nthree\n", 1), "three\n");
+    }
+
+    #[test]
+    fn tail_without_final_newline() {
+        assert_eq!(tail("unterminated", "one\ntwo\nthree", 1), "three");
+        assert_eq!(tail("unterminated-two", "one\ntwo\nthree", 2), "two\nthree");
+    }
+
+    #[test]
+    fn tail_keeps_crlf() {
+        assert_eq!(
+            tail("crlf", "one\r\ntwo\r\nthree\r\n", 2),
+            "two\r\nthree\r\n"
+        );
+    }
+
+    #[test]
+    fn tail_longer_than_file_is_whole_file() {
+        assert_eq!(tail("whole", "one\ntwo\n", 5), "one\ntwo\n");
+        assert_eq!(tail("whole-single", "only line", 3), "only line");
+        assert_eq!(tail("empty", "", 1), "");
+    }
+
+    #[test]
+    fn tail_across_chunks() {
+        let line = format!("{}\n", "x".repeat(99));
+        let lines = (TAIL_CHUNK as usize / line.len()) * 3;
+        let content = line.repeat(lines);
+        assert_eq!(tail("chunks", &content, lines - 1), line.repeat(lines - 1));
+        assert_eq!(tail("chunks-all", &content, lines + 1), content);
+    }
+}
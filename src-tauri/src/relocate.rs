@@ -0,0 +1,77 @@
+use std::{fs, path::PathBuf};
+
+use crate::{cache, ensure_notes_dir, fsutil, links, settings, watcher};
+
+/// Moves the whole library (notes and every internal folder/sidecar) to
+/// `new_path` and points the app at it. The old location is only removed once
+/// the copy has been verified and the new path saved; any earlier failure
+/// leaves the original untouched and cleans up the partial copy.
+#[tauri::command]
+pub fn relocate_notes_dir(app: tauri::AppHandle, new_path: String) -> Result<(), String> {
+    let old = ensure_notes_dir(&app)?;
+    let new = PathBuf::from(new_path.trim());
+    if new.as_os_str().is_empty() {
+        return Err("New notes dir must not be empty".to_string());
+    }
+
+    let old_abs =
+        fs::canonicalize(&old).map_err(|e| format!("Failed to resolve notes dir: {e}"))?;
+    let new_abs = if new.is_absolute() {
+        new.clone()
+    } else {
+        std::env::current_dir()
+            .map_err(|e| format!("Failed to resolve new notes dir: {e}"))?
+            .join(&new)
+    };
+    if new_abs.starts_with(&old_abs) || old_abs.starts_with(&new_abs) {
+        return Err("New notes dir must not contain or be inside the current one".to_string());
+    }
+    if new.exists() && !fsutil::is_empty_dir(&new) {
+        return Err("New notes dir must be empty".to_string());
+    }
+    let created_new = !new.exists();
+
+    let staged = fsutil::copy_dir(&old, &new)
+        .and_then(|_| fsutil::verify_copy(&old, &new))
+        .and_then(|_| {
+            let mut current = settings::load(&app)?;
+            // Relative input would resolve against whatever the next
+            // launch's working dir is.
+            current.notes_dir = Some(new_abs.to_string_lossy().to_string());
+            settings::save(&app, &current)
+        });
+    if let Err(e) = staged {
+        let cleanup = if created_new {
+            fs::remove_dir_all(&new)
+        } else {
+            fs::read_dir(&new).and_then(|entries| {
+                entries.filter_map(Result::ok).try_for_each(|entry| {
+                    let path = entry.path();
+                    if path.is_dir() {
+                        fs::remove_dir_all(path)
+                    } else {
+                        fs::remove_file(path)
+                    }
+                })
+            })
+        };
+        if let Err(cleanup) = cleanup {
+            eprintln!(
+                "Failed to clean up partial copy at {}: {cleanup}",
+                new.display()
+            );
+        }
+        return Err(format!("Failed to relocate notes dir: {e}"));
+    }
+
+    // The library is safe at the new location from here on.
+    if let Err(e) = watcher::start(&app, &new_abs) {
+        eprintln!("{e}");
+    }
+    cache::clear(&app);
+    links::invalidate(&app);
+    if let Err(e) = fs::remove_dir_all(&old) {
+        eprintln!("Failed to remove old notes dir {}: {e}", old.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,235 @@
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use syntect::{
+    highlighting::{Theme, ThemeSet},
+    html::highlighted_html_for_string,
+    parsing::{SyntaxReference, SyntaxSet},
+};
+
+use crate::{feed::xml_escape, frontmatter, markdown::is_fence, settings};
+
+// A light theme, matching the default preview styles.
+const HIGHLIGHT_THEME: &str = "InspiredGitHub";
+
+/// Callout types (`> [!note]`) rendered as `<div class="callout callout-note">`
+/// by `render_markdown`. Matching ignores case; any other type stays a plain
+/// blockquote.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CalloutTypes(pub Vec<String>);
+
+impl Default for CalloutTypes {
+    fn default() -> Self {
+        Self(
+            [
+                "note", "abstract", "info", "todo", "tip", "success", "question", "warning",
+                "failure", "danger", "bug", "example", "quote",
+            ]
+            .map(String::from)
+            .to_vec(),
+        )
+    }
+}
+
+/// Syntax definitions and theme, loaded on first use: parsing them takes
+/// long enough to notice, and plain previews never need them.
+fn highlighting() -> &'static (SyntaxSet, Theme) {
+    static HIGHLIGHTING: OnceLock<(SyntaxSet, Theme)> = OnceLock::new();
+    HIGHLIGHTING.get_or_init(|| {
+        let mut themes = ThemeSet::load_defaults();
+        let theme = themes.themes.remove(HIGHLIGHT_THEME).unwrap_or_default();
+        (SyntaxSet::load_defaults_newlines(), theme)
+    })
+}
+
+fn options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES
+}
+
+// Callouts carry their type as a class; nothing else keeps one.
+fn sanitizer() -> ammonia::Builder<'static> {
+    let mut builder = ammonia::Builder::default();
+    builder
+        .add_tag_attributes("div", &["class"])
+        .add_tag_attributes("p", &["class"]);
+    builder
+}
+
+/// Sanitized HTML for a note's body. Frontmatter is not part of the rendering.
+pub fn to_html(content: &str) -> String {
+    body_html(frontmatter::body(content))
+}
+
+fn body_html(body: &str) -> String {
+    let mut out = String::new();
+    html::push_html(&mut out, Parser::new_ext(body, options()));
+    sanitizer().clean(&out).to_string()
+}
+
+/// Type and title of a callout's first line (`> [!type] Title`, optionally
+/// foldable with `+`/`-` after the bracket), when the type is one of `types`.
+fn callout_header<'a>(line: &'a str, types: &[String]) -> Option<(String, &'a str)> {
+    let rest = line.trim_start().strip_prefix('>')?.trim_start();
+    let (kind, title) = rest.strip_prefix("[!")?.split_once(']')?;
+    let kind = kind.trim().to_lowercase();
+    let valid = kind
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if kind.is_empty() || !valid || !types.iter().any(|t| t.eq_ignore_ascii_case(&kind)) {
+        return None;
+    }
+    Some((kind, title.trim_start_matches(['+', '-']).trim()))
+}
+
+/// Rewrites callout blockquotes of a known type into `<div>` blocks around
+/// their (still markdown) content. Untitled callouts are titled after their
+/// type.
+pub fn expand_callouts(body: &str, types: &[String]) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut lines = body.lines().peekable();
+    let mut in_fence = false;
+    while let Some(line) = lines.next() {
+        if is_fence(line) {
+            in_fence = !in_fence;
+        }
+        let Some((kind, title)) = callout_header(line, types).filter(|_| !in_fence) else {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        };
+
+        let title = match title {
+            "" => {
+                let mut chars = kind.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                    .unwrap_or_default()
+            }
+            title => title.to_string(),
+        };
+        // The blank lines end the HTML blocks, so the content in between is
+        // parsed as markdown again.
+        out.push_str(&format!(
+            "<div class=\"callout callout-{kind}\">\n<p class=\"callout-title\">{}</p>\n\n",
+            xml_escape(&title)
+        ));
+        while let Some(next) = lines.next_if(|next| next.trim_start().starts_with('>')) {
+            let inner = &next.trim_start()[1..];
+            out.push_str(inner.strip_prefix(' ').unwrap_or(inner));
+            out.push('\n');
+        }
+        out.push_str("\n</div>\n\n");
+    }
+    out
+}
+
+/// Highlighted HTML for a fenced code block, or `None` to render it as a
+/// plain `<pre>` (unknown language, or syntect failed).
+fn highlight_block(syntax: &SyntaxReference, code: &str) -> Option<String> {
+    let (syntaxes, theme) = highlighting();
+    highlighted_html_for_string(code, syntaxes, syntax, theme).ok()
+}
+
+/// Like [`body_html`], but fenced code blocks in a language syntect knows are
+/// syntax highlighted with inline styles.
+fn body_html_highlighted(body: &str) -> String {
+    let (syntaxes, _) = highlighting();
+    let mut events = Vec::new();
+    let mut code: Option<(&SyntaxReference, String)> = None;
+    for event in Parser::new_ext(body, options()) {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info))) => {
+                let lang = info.split_whitespace().next().unwrap_or_default();
+                match syntaxes
+                    .find_syntax_by_token(lang)
+                    .filter(|_| !lang.is_empty())
+                {
+                    Some(syntax) => code = Some((syntax, String::new())),
+                    None => events.push(event),
+                }
+            }
+            Event::Text(text) if code.is_some() => {
+                if let Some((_, buffer)) = code.as_mut() {
+                    buffer.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) if code.is_some() => {
+                let Some((syntax, text)) = code.take() else {
+                    continue;
+                };
+                let html = highlight_block(syntax, &text)
+                    .unwrap_or_else(|| format!("<pre><code>{}</code></pre>\n", xml_escape(&text)));
+                events.push(Event::Html(CowStr::from(html)));
+            }
+            event => events.push(event),
+        }
+    }
+
+    let mut out = String::new();
+    html::push_html(&mut out, events.into_iter());
+    // syntect colors spans (and the block background) with inline styles.
+    sanitizer()
+        .add_tag_attributes("pre", &["style"])
+        .add_tag_attributes("span", &["style"])
+        .clean(&out)
+        .to_string()
+}
+
+// Ends the current block with exactly one blank line.
+fn end_block(out: &mut String) {
+    let trimmed = out.trim_end_matches('\n').len();
+    out.truncate(trimmed);
+    out.push_str("\n\n");
+}
+
+/// Plain text of a note's body with the markdown syntax dropped, suitable for
+/// pasting into e-mail or other rich-text-unaware places.
+pub fn to_plaintext(content: &str) -> String {
+    let mut out = String::new();
+    for event in Parser::new_ext(frontmatter::body(content), options()) {
+        match event {
+            Event::Text(text) | Event::Code(text) => out.push_str(&text),
+            Event::SoftBreak | Event::HardBreak => out.push('\n'),
+            Event::Start(Tag::List(_)) if !out.is_empty() && !out.ends_with('\n') => out.push('\n'),
+            Event::Start(Tag::Item) => out.push_str("- "),
+            Event::End(TagEnd::Item) => {
+                let trimmed = out.trim_end_matches('\n').len();
+                out.truncate(trimmed);
+                out.push('\n');
+            }
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::CodeBlock
+                | TagEnd::BlockQuote(_)
+                | TagEnd::List(_),
+            )
+            | Event::Rule => end_block(&mut out),
+            _ => {}
+        }
+    }
+    out.trim_end().to_string()
+}
+
+/// With `highlight`, fenced code blocks are syntax highlighted; lightweight
+/// previews can leave it off and skip the cost. Callouts of the types in the
+/// `callout_types` setting become styled blocks.
+#[tauri::command]
+pub fn render_markdown(
+    app: tauri::AppHandle,
+    content: String,
+    highlight: Option<bool>,
+) -> Result<String, String> {
+    let types = settings::load(&app)?.callout_types;
+    let body = expand_callouts(frontmatter::body(&content), &types.0);
+    if highlight.unwrap_or(false) {
+        Ok(body_html_highlighted(&body))
+    } else {
+        Ok(body_html(&body))
+    }
+}
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    thread,
+    time::{Duration, SystemTime},
+};
+use tauri::Manager;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::{
+    ensure_notes_dir, locate_note, metadata, note_title, pin, search, settings, sidecar,
+    system_time_ms,
+};
+
+const RUN_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Titles spelled out in a notification before it switches to "and N more".
+const NOTIFY_TITLES: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
+    #[serde(default)]
+    pub case_insensitive: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RunState {
+    last_run_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedSearchHit {
+    name: String,
+    /// Notes changed since the previous run that match the query.
+    ids: Vec<String>,
+}
+
+fn state_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let base = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {e}"))?;
+    Ok(base.join("saved_searches.json"))
+}
+
+fn notify(app: &tauri::AppHandle, search: &SavedSearch, titles: &[String]) {
+    let mut body = titles
+        .iter()
+        .take(NOTIFY_TITLES)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if titles.len() > NOTIFY_TITLES {
+        body.push_str(&format!(" and {} more", titles.len() - NOTIFY_TITLES));
+    }
+    let shown = app
+        .notification()
+        .builder()
+        .title(format!("Saved search: {}", search.name))
+        .body(body)
+        .show();
+    if let Err(e) = shown {
+        eprintln!("Failed to show notification: {e}");
+    }
+}
+
+/// Checks every saved search against the notes modified since the previous
+/// run and notifies about matches, so each change is reported once. The very
+/// first run only records the time.
+pub fn run(app: &tauri::AppHandle) -> Result<Vec<SavedSearchHit>, String> {
+    // Notifications show note titles, which a locked app must not reveal.
+    // The last-run time stays put, so nothing is missed after unlocking.
+    pin::ensure_unlocked(app)?;
+    let searches = settings::load(app)?.saved_searches;
+    let dir = ensure_notes_dir(app)?;
+    let storage = metadata::active(app)?;
+    let path = state_path(app)?;
+    let mut state: RunState = sidecar::load(&path)?;
+    let now = system_time_ms(SystemTime::now());
+
+    let mut results = Vec::new();
+    if let Some(since) = state.last_run_ms {
+        for search in &searches {
+            if search.query.is_empty() {
+                continue;
+            }
+            let hits = search::matching_notes(
+                app,
+                &dir,
+                &search.query,
+                search.case_insensitive,
+                Some(since),
+            )?;
+            if hits.is_empty() {
+                continue;
+            }
+
+            let mut titles = Vec::new();
+            for hit in &hits {
+                let content = metadata::read_view(storage, &locate_note(&dir, &hit.id))?;
+                titles.push(note_title(&hit.id, &content));
+            }
+            notify(app, search, &titles);
+            results.push(SavedSearchHit {
+                name: search.name.clone(),
+                ids: hits.into_iter().map(|hit| hit.id).collect(),
+            });
+        }
+    }
+
+    state.last_run_ms = Some(now);
+    sidecar::save(&path, &state)?;
+    Ok(results)
+}
+
+/// Runs the saved searches every few minutes for the app's lifetime.
+pub fn start_saved_search_task(app: &tauri::AppHandle) {
+    let app = app.clone();
+    thread::spawn(move || loop {
+        if !pin::is_locked(&app) {
+            if let Err(e) = run(&app) {
+                eprintln!("Failed to run saved searches: {e}");
+            }
+        }
+        thread::sleep(RUN_INTERVAL);
+    });
+}
+
+#[tauri::command]
+pub fn run_saved_searches(app: tauri::AppHandle) -> Result<Vec<SavedSearchHit>, String> {
+    run(&app)
+}
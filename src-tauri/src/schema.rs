@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+use crate::{
+    cache, dates, ensure_notes_dir, file_times_ms, frontmatter, library_notes, metadata,
+    read_note_content, settings,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    List,
+    Number,
+    Boolean,
+    /// `YYYY-MM-DD`, optionally followed by a time.
+    Date,
+}
+
+/// A frontmatter field every note is expected to have.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaField {
+    pub key: String,
+    #[serde(rename = "type")]
+    pub kind: FieldType,
+    /// Value written by `apply_schema_defaults`; lists take comma-separated
+    /// items. A date field without one defaults to the file's creation day.
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Problem {
+    Missing,
+    WrongType,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    id: String,
+    key: String,
+    expected: FieldType,
+    problem: Problem,
+}
+
+fn is_date(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes.len() >= 10
+        && bytes[..10].iter().enumerate().all(|(i, b)| match i {
+            4 | 7 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        })
+        && (bytes.len() == 10 || matches!(bytes[10], b'T' | b' '))
+}
+
+fn check(content: &str, field: &SchemaField) -> Option<Problem> {
+    let value = frontmatter::field(content, &field.key);
+    let Some(value) = value else {
+        // An empty inline value can still be the head of a block list.
+        let is_list = !frontmatter::list(content, &field.key).is_empty();
+        return Some(match (is_list, field.kind) {
+            (false, _) => Problem::Missing,
+            (true, FieldType::List) => return None,
+            (true, _) => Problem::WrongType,
+        });
+    };
+
+    let ok = match field.kind {
+        FieldType::String | FieldType::List => true,
+        FieldType::Number => value.parse::<f64>().is_ok_and(f64::is_finite),
+        FieldType::Boolean => matches!(value.as_str(), "true" | "false"),
+        FieldType::Date => is_date(&value),
+    };
+    (!ok).then_some(Problem::WrongType)
+}
+
+/// The formatted default of `field` for the note at `path`, if it has one.
+fn default_value(field: &SchemaField, path: &Path) -> Result<Option<String>, String> {
+    let Some(default) = field.default.as_deref() else {
+        if field.kind != FieldType::Date {
+            return Ok(None);
+        }
+        let (created, _) = file_times_ms(path)?;
+        return Ok(Some(dates::format_day(dates::day_of(created))));
+    };
+
+    Ok(Some(match field.kind {
+        FieldType::List => {
+            let items: Vec<String> = default
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect();
+            frontmatter::inline_list(&items)
+        }
+        FieldType::String => frontmatter::scalar(default),
+        _ => default.trim().to_string(),
+    }))
+}
+
+#[tauri::command]
+pub fn validate_schema(app: tauri::AppHandle) -> Result<Vec<SchemaViolation>, String> {
+    let schema = settings::load(&app)?.schema;
+    let dir = ensure_notes_dir(&app)?;
+    let storage = metadata::active(&app)?;
+
+    let mut violations = Vec::new();
+    for (id, path) in library_notes(&app, &dir)? {
+        let content = metadata::read_view(storage, &path)?;
+        for field in &schema {
+            if let Some(problem) = check(&content, field) {
+                violations.push(SchemaViolation {
+                    id: id.clone(),
+                    key: field.key.clone(),
+                    expected: field.kind,
+                    problem,
+                });
+            }
+        }
+    }
+    violations.sort_by(|a, b| a.id.cmp(&b.id).then_with(|| a.key.cmp(&b.key)));
+    Ok(violations)
+}
+
+/// Fills in missing schema fields that have a default. Fields with a value of
+/// the wrong type are left for the user to fix. Returns the number of notes
+/// changed.
+#[tauri::command]
+pub fn apply_schema_defaults(app: tauri::AppHandle) -> Result<usize, String> {
+    let schema = settings::load(&app)?.schema;
+    let dir = ensure_notes_dir(&app)?;
+    let storage = metadata::active(&app)?;
+
+    let mut changed = 0usize;
+    for (_, path) in library_notes(&app, &dir)? {
+        let content = read_note_content(&path)?;
+        let shown = metadata::view(storage, &path, content.clone())?;
+        let mut updated = content.clone();
+        let mut filled = false;
+        for field in &schema {
+            if check(&shown, field) != Some(Problem::Missing) {
+                continue;
+            }
+            if let Some(value) = default_value(field, &path)? {
+                updated = metadata::set_raw(storage, &path, &updated, &field.key, Some(&value))?;
+                filled = true;
+            }
+        }
+        if updated != content {
+            fs::write(&path, updated).map_err(|e| format!("Failed to write note file: {e}"))?;
+        }
+        if filled {
+            changed += 1;
+        }
+    }
+    if changed > 0 {
+        cache::clear(&app);
+    }
+    Ok(changed)
+}
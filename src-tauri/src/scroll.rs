@@ -0,0 +1,71 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+use tauri::Manager;
+
+use crate::{ensure_notes_dir, existing_note_path, sanitize_id, sidecar, transaction::Transaction};
+
+/// Note id -> scroll ratio (0.0 top, 1.0 bottom). Reader state rather than
+/// library content, so it lives in the app data dir.
+type ScrollPositions = HashMap<String, f64>;
+
+fn scroll_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let base = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {e}"))?;
+    Ok(base.join("scroll.json"))
+}
+
+/// Stages scroll positions moved to renamed ids as part of `txn`.
+pub fn stage_renames(
+    app: &tauri::AppHandle,
+    txn: &mut Transaction,
+    renames: &HashMap<String, String>,
+) -> Result<(), String> {
+    sidecar::stage_rekey::<f64>(txn, &scroll_path(app)?, renames)
+}
+
+/// Drops positions of notes that no longer exist. Returns the ids removed.
+pub fn retain_positions(
+    app: &tauri::AppHandle,
+    ids: &HashSet<String>,
+) -> Result<Vec<String>, String> {
+    let path = scroll_path(app)?;
+    let mut positions: ScrollPositions = sidecar::load(&path)?;
+    let mut dropped: Vec<String> = positions
+        .keys()
+        .filter(|id| !ids.contains(*id))
+        .cloned()
+        .collect();
+    if dropped.is_empty() {
+        return Ok(dropped);
+    }
+
+    positions.retain(|id, _| ids.contains(id));
+    sidecar::save(&path, &positions)?;
+    dropped.sort();
+    Ok(dropped)
+}
+
+#[tauri::command]
+pub fn set_scroll_position(app: tauri::AppHandle, id: String, ratio: f64) -> Result<(), String> {
+    let dir = ensure_notes_dir(&app)?;
+    existing_note_path(&dir, &id)?;
+    if ratio.is_nan() {
+        return Err("Scroll position must be a number".to_string());
+    }
+
+    let path = scroll_path(&app)?;
+    let mut positions: ScrollPositions = sidecar::load(&path)?;
+    positions.insert(sanitize_id(&id), ratio.clamp(0.0, 1.0));
+    sidecar::save(&path, &positions)
+}
+
+/// The saved ratio, or `None` when the note was never scrolled.
+#[tauri::command]
+pub fn get_scroll_position(app: tauri::AppHandle, id: String) -> Result<Option<f64>, String> {
+    let positions: ScrollPositions = sidecar::load(&scroll_path(&app)?)?;
+    Ok(positions.get(&sanitize_id(&id)).copied())
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+
+use std::path::Path;
+
+use crate::{
+    ensure_notes_dir, existing_note_path, file_times_ms, library_notes, pin, read_note_content,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Match {
+    /// Byte offset of the match in the note content.
+    offset: usize,
+    /// Length of the matched text in bytes.
+    len: usize,
+    /// 1-based line number.
+    line: usize,
+    /// 1-based column, counted in characters.
+    column: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub id: String,
+    /// Number of occurrences of the query in the note.
+    pub matches: usize,
+}
+
+/// Byte length of the text at the start of `haystack` matching `needle`, if any.
+fn match_len(haystack: &str, needle: &str, case_insensitive: bool) -> Option<usize> {
+    if !case_insensitive {
+        return haystack.starts_with(needle).then_some(needle.len());
+    }
+
+    // Char-by-char comparison, so the reported length always refers to the
+    // original text even where lowercasing changes byte lengths.
+    let mut hay = haystack.char_indices();
+    let mut end = 0;
+    for n in needle.chars() {
+        let (i, h) = hay.next()?;
+        if !h.to_lowercase().eq(n.to_lowercase()) {
+            return None;
+        }
+        end = i + h.len_utf8();
+    }
+    Some(end)
+}
+
+/// Non-overlapping occurrences of `query` in `content`.
+pub fn find_matches(content: &str, query: &str, case_insensitive: bool) -> Vec<Match> {
+    let mut matches = Vec::new();
+    let mut line = 1;
+    let mut column = 1;
+    let mut skip_until = 0;
+
+    for (offset, ch) in content.char_indices() {
+        if offset >= skip_until {
+            if let Some(len) = match_len(&content[offset..], query, case_insensitive) {
+                matches.push(Match {
+                    offset,
+                    len,
+                    line,
+                    column,
+                });
+                skip_until = offset + len;
+            }
+        }
+
+        if ch == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+    matches
+}
+
+#[tauri::command]
+pub fn search_in_note(
+    app: tauri::AppHandle,
+    id: String,
+    query: String,
+    case_insensitive: Option<bool>,
+) -> Result<Vec<Match>, String> {
+    pin::ensure_unlocked(&app)?;
+    if query.is_empty() {
+        return Err("Query must not be empty".to_string());
+    }
+
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    let content = read_note_content(&path)?;
+    Ok(find_matches(
+        &content,
+        &query,
+        case_insensitive.unwrap_or(false),
+    ))
+}
+
+/// Notes in `dir` containing `query`, most matches first. With
+/// `modified_after`, only notes changed after that time are searched.
+pub fn matching_notes(
+    app: &tauri::AppHandle,
+    dir: &Path,
+    query: &str,
+    case_insensitive: bool,
+    modified_after: Option<u64>,
+) -> Result<Vec<SearchHit>, String> {
+    let mut hits = Vec::new();
+    for (id, path) in library_notes(app, dir)? {
+        if let Some(after) = modified_after {
+            if file_times_ms(&path)?.1 <= after {
+                continue;
+            }
+        }
+        let matches = find_matches(&read_note_content(&path)?, query, case_insensitive).len();
+        if matches > 0 {
+            hits.push(SearchHit { id, matches });
+        }
+    }
+    hits.sort_by(|a, b| b.matches.cmp(&a.matches).then_with(|| a.id.cmp(&b.id)));
+    Ok(hits)
+}
+
+#[tauri::command]
+pub fn search_notes(
+    app: tauri::AppHandle,
+    query: String,
+    case_insensitive: Option<bool>,
+) -> Result<Vec<SearchHit>, String> {
+    pin::ensure_unlocked(&app)?;
+    if query.is_empty() {
+        return Err("Query must not be empty".to_string());
+    }
+
+    let dir = ensure_notes_dir(&app)?;
+    matching_notes(&app, &dir, &query, case_insensitive.unwrap_or(false), None)
+}
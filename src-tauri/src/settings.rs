@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::Manager;
+
+use crate::{
+    confirm::DangerousOperations, ids::IdStrategy, layout::Layout, metadata::MetadataStorage,
+    render::CalloutTypes, saved_searches::SavedSearch, schema::SchemaField, sidecar,
+};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Commit every note change when the notes dir is inside a git repository.
+    pub git_autocommit: bool,
+    /// Id scheme for newly created notes.
+    pub id_strategy: IdStrategy,
+    /// Custom location of the notes dir; `None` keeps it in the app data dir.
+    pub notes_dir: Option<String>,
+    /// Reject titles another note already uses (case-insensitive).
+    pub enforce_unique_titles: bool,
+    /// Frontmatter fields every note should have, checked by `validate_schema`.
+    pub schema: Vec<SchemaField>,
+    /// Count `#hashtags` in note bodies as tags, alongside frontmatter `tags`.
+    pub inline_hashtags: bool,
+    /// Queries checked in the background; matches in changed notes notify.
+    pub saved_searches: Vec<SavedSearch>,
+    /// Commands that need `confirmed: true` to run.
+    pub dangerous_operations: DangerousOperations,
+    /// Where published notes live online; permalinks are `<base>/<slug>`.
+    pub publish_base_url: Option<String>,
+    /// Store history versions as diffs against the version before them.
+    pub history_diffs: bool,
+    /// Free space (bytes) that must remain after saving a note; `None` keeps
+    /// the default of 10 MiB.
+    pub disk_space_margin: Option<u64>,
+    /// When a title is changed through `update_frontmatter`, rename the file
+    /// to a slug of it. Only this direction is synced: renaming a file never
+    /// touches its title.
+    pub sync_title_to_filename: bool,
+    /// Callout types `render_markdown` styles; others stay blockquotes.
+    pub callout_types: CalloutTypes,
+    /// Hours between scheduled backups; 0 turns them off.
+    pub auto_backup_interval_hours: u64,
+    /// Backups to keep after a scheduled one; older ones are deleted. 0
+    /// keeps them all.
+    pub backup_retention: usize,
+    /// Whether notes live in folders; change it with `set_storage_layout` so
+    /// the files move too.
+    pub storage_layout: Layout,
+    /// Where title, tags, pinned and color are kept; change it with
+    /// `set_metadata_storage` so the notes are converted too.
+    pub metadata_storage: MetadataStorage,
+}
+
+fn settings_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let base = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {e}"))?;
+    Ok(base.join("settings.json"))
+}
+
+/// Current settings; a missing file means everything is at its default.
+pub fn load(app: &tauri::AppHandle) -> Result<Settings, String> {
+    sidecar::load(&settings_path(app)?)
+}
+
+pub fn save(app: &tauri::AppHandle, settings: &Settings) -> Result<(), String> {
+    sidecar::save(&settings_path(app)?, settings)
+}
+
+#[tauri::command]
+pub fn get_settings(app: tauri::AppHandle) -> Result<Settings, String> {
+    load(&app)
+}
+
+#[tauri::command]
+pub fn update_settings(app: tauri::AppHandle, settings: Settings) -> Result<(), String> {
+    save(&app, &settings)
+}
@@ -0,0 +1,36 @@
+use std::path::Path;
+
+use crate::{ensure_notes_dir, existing_note_path};
+
+#[cfg(windows)]
+fn add_recent_document(path: &Path) -> Result<(), String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::Win32::UI::Shell::{SHAddToRecentDocs, SHARD_PATHW};
+
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve note path: {e}"))?;
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+    // Adding to recent documents is also what puts the file in the app's
+    // jump list under "Recent".
+    unsafe { SHAddToRecentDocs(SHARD_PATHW.0 as u32, Some(wide.as_ptr().cast())) };
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn add_recent_document(path: &Path) -> Result<(), String> {
+    eprintln!(
+        "Quick access is not supported on this platform; skipped {}",
+        path.display()
+    );
+    Ok(())
+}
+
+/// Registers the note file with the OS shell so it can be reopened from the
+/// taskbar jump list. A no-op (with a warning) where that isn't supported.
+#[tauri::command]
+pub fn add_to_quick_access(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    add_recent_document(&path)
+}
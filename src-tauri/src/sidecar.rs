@@ -0,0 +1,72 @@
+// JSON files the app keeps next to the notes (library metadata, settings, ...).
+use serde::{de::DeserializeOwned, Serialize};
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::transaction::Transaction;
+
+fn name_of(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// Reads `path`, falling back to `T::default()` when the file doesn't exist yet.
+pub fn load<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    match fs::read_to_string(path) {
+        Ok(raw) => serde_json::from_str(&raw)
+            .map_err(|e| format!("Failed to parse {}: {e}", name_of(path))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(format!("Failed to read {}: {e}", name_of(path))),
+    }
+}
+
+fn to_json<T: Serialize>(path: &Path, value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {e}", name_of(path)))
+}
+
+/// Stages the id-keyed map at `path` with its keys renamed per `renames`
+/// (old id -> new id) as part of `txn`. Stages nothing when no key moves.
+pub fn stage_rekey<V: Serialize + DeserializeOwned>(
+    txn: &mut Transaction,
+    path: &Path,
+    renames: &HashMap<String, String>,
+) -> Result<(), String> {
+    let mut map: HashMap<String, V> = load(path)?;
+    if !rekey(&mut map, renames) {
+        return Ok(());
+    }
+    stage(txn, path, &map)
+}
+
+/// Stages `value` as the new content of `path` in `txn`.
+pub fn stage<T: Serialize>(txn: &mut Transaction, path: &Path, value: &T) -> Result<(), String> {
+    txn.write(path, to_json(path, value)?);
+    Ok(())
+}
+
+/// Moves the entries of `map` from old to new ids. Returns whether any moved.
+pub fn rekey<V>(map: &mut HashMap<String, V>, renames: &HashMap<String, String>) -> bool {
+    let mut moved = false;
+    for (old, new) in renames {
+        if let Some(value) = map.remove(old) {
+            map.insert(new.clone(), value);
+            moved = true;
+        }
+    }
+    moved
+}
+
+/// Writes `value` through a temp file and a rename, so a crash mid-write
+/// never leaves a truncated file behind.
+pub fn save<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create dir for {}: {e}", name_of(path)))?;
+    }
+    let raw = to_json(path, value)?;
+
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, raw).map_err(|e| format!("Failed to write {}: {e}", name_of(path)))?;
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to write {}: {e}", name_of(path)))
+}
@@ -0,0 +1,131 @@
+// Near-duplicate detection with MinHash signatures and locality-sensitive
+// hashing: only notes that share a signature band are compared exactly, so
+// the work stays close to linear in the number of notes.
+
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+use crate::{ensure_notes_dir, frontmatter, hash, library_notes, read_note_content};
+
+/// Words per shingle.
+const SHINGLE_WORDS: usize = 3;
+/// Shorter words are mostly function words and say little about the topic.
+const MIN_KEYWORD_CHARS: usize = 4;
+const BANDS: usize = 16;
+const ROWS_PER_BAND: usize = 4;
+const SIGNATURE_LEN: usize = BANDS * ROWS_PER_BAND;
+
+/// Hashes of the overlapping word shingles of the note body, compared
+/// case-insensitively. Notes shorter than a shingle use the whole body.
+fn shingles(content: &str) -> HashSet<u64> {
+    let words: Vec<String> = frontmatter::body(content)
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect();
+    let size = SHINGLE_WORDS.min(words.len()).max(1);
+    words
+        .windows(size)
+        .map(|window| hash::fnv1a64(window.join(" ").as_bytes()))
+        .collect()
+}
+
+// splitmix64 finalizer: turns `shingle ^ seed` into an independent-looking
+// hash per signature row.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+fn signature(shingles: &HashSet<u64>) -> [u64; SIGNATURE_LEN] {
+    let mut sig = [u64::MAX; SIGNATURE_LEN];
+    for &shingle in shingles {
+        for (row, slot) in sig.iter_mut().enumerate() {
+            let seed = (row as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+            *slot = (*slot).min(mix(shingle ^ seed));
+        }
+    }
+    sig
+}
+
+pub fn jaccard<T: Eq + Hash>(a: &HashSet<T>, b: &HashSet<T>) -> f64 {
+    let shared = a.intersection(b).count();
+    match a.len() + b.len() - shared {
+        0 => 0.0,
+        union => shared as f64 / union as f64,
+    }
+}
+
+/// Distinct lowercase words of the note body worth comparing topics by.
+pub fn keywords(content: &str) -> HashSet<String> {
+    frontmatter::body(content)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= MIN_KEYWORD_CHARS)
+        .filter(|word| !word.chars().all(|c| c.is_ascii_digit()))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Pairs of notes whose shingle sets have a Jaccard similarity of at least
+/// `threshold`, most similar first. Candidates come from LSH, so pairs well
+/// below ~0.5 similarity may be missed; every reported score is exact.
+#[tauri::command]
+pub fn near_duplicates(
+    app: tauri::AppHandle,
+    threshold: f64,
+) -> Result<Vec<(String, String, f64)>, String> {
+    if threshold.is_nan() || threshold <= 0.0 || threshold > 1.0 {
+        return Err("Threshold must be greater than 0 and at most 1".to_string());
+    }
+    let dir = ensure_notes_dir(&app)?;
+
+    let mut notes: Vec<(String, HashSet<u64>)> = Vec::new();
+    for (id, path) in library_notes(&app, &dir)? {
+        let set = shingles(&read_note_content(&path)?);
+        if !set.is_empty() {
+            notes.push((id, set));
+        }
+    }
+    notes.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut buckets: HashMap<(usize, u64), Vec<usize>> = HashMap::new();
+    for (index, (_, set)) in notes.iter().enumerate() {
+        let sig = signature(set);
+        for (band, rows) in sig.chunks(ROWS_PER_BAND).enumerate() {
+            let key = rows.iter().fold(0u64, |acc, &row| mix(acc ^ row));
+            buckets.entry((band, key)).or_default().push(index);
+        }
+    }
+
+    let mut candidates: HashSet<(usize, usize)> = HashSet::new();
+    for members in buckets.values() {
+        for (i, &a) in members.iter().enumerate() {
+            for &b in &members[i + 1..] {
+                candidates.insert((a, b));
+            }
+        }
+    }
+
+    let mut pairs = Vec::new();
+    for (a, b) in candidates {
+        let (set_a, set_b) = (&notes[a].1, &notes[b].1);
+        // Jaccard can't exceed the size ratio, so lopsided pairs are skipped
+        // without touching their contents.
+        let (small, large) = (set_a.len().min(set_b.len()), set_a.len().max(set_b.len()));
+        if (small as f64) < threshold * large as f64 {
+            continue;
+        }
+        let score = jaccard(set_a, set_b);
+        if score >= threshold {
+            pairs.push((notes[a].0.clone(), notes[b].0.clone(), score));
+        }
+    }
+    pairs.sort_by(|a, b| {
+        b.2.total_cmp(&a.2)
+            .then_with(|| a.0.cmp(&b.0))
+            .then_with(|| a.1.cmp(&b.1))
+    });
+    Ok(pairs)
+}
@@ -0,0 +1,461 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::Path,
+};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::{
+    dates, ensure_notes_dir, existing_note_path, file_times_ms, frontmatter, history,
+    library_notes, markdown::lines_with_fences, note_files, pin, read_note_content, sanitize_id,
+    system_time_ms, trash, TRASH_DIR,
+};
+
+// Common English function words; they'd top every report otherwise.
+const STOPWORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "an", "and", "any", "are", "as", "at", "be", "because",
+    "been", "but", "by", "can", "could", "did", "do", "does", "for", "from", "had", "has", "have",
+    "he", "her", "his", "how", "i", "if", "in", "into", "is", "it", "its", "just", "me", "more",
+    "my", "no", "not", "of", "on", "one", "or", "our", "out", "she", "so", "some", "than", "that",
+    "the", "their", "them", "then", "there", "these", "they", "this", "to", "up", "us", "was",
+    "we", "were", "what", "when", "which", "who", "will", "with", "would", "you", "your",
+];
+
+// Keeps a mistaken range (e.g. seconds passed as ms) from allocating a huge grid.
+const MAX_HEATMAP_DAYS: i64 = 366 * 20;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DayCount {
+    /// UTC calendar day, `YYYY-MM-DD`.
+    date: String,
+    count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DigestNote {
+    id: String,
+    /// Words gained (negative: lost) over the day; `None` when history
+    /// doesn't reach back far enough to tell.
+    words_delta: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Digest {
+    /// UTC calendar day, `YYYY-MM-DD`.
+    date: String,
+    created: Vec<DigestNote>,
+    /// Notes changed that day but created earlier.
+    modified: Vec<DigestNote>,
+    /// Notes moved to the trash that day.
+    deleted: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoteWords {
+    id: String,
+    words: usize,
+}
+
+/// Longest and shortest non-empty notes. Every field is `None` when the
+/// library has no note with any words in it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Extremes {
+    longest: Option<NoteWords>,
+    shortest: Option<NoteWords>,
+    /// Mean word count over the non-empty notes.
+    average_words: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bucket {
+    Day,
+    /// Weeks start on Monday.
+    Week,
+    Month,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BucketCount {
+    /// First day of the bucket, `YYYY-MM-DD`.
+    start: String,
+    count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StreakInfo {
+    /// Consecutive days with an edit, ending today or yesterday.
+    current: usize,
+    longest: usize,
+    /// Most recent day with an edit, `YYYY-MM-DD`.
+    last_day: Option<String>,
+}
+
+/// Whitespace-separated words in the note body; frontmatter doesn't count.
+pub fn word_count(content: &str) -> usize {
+    frontmatter::body(content).split_whitespace().count()
+}
+
+#[tauri::command]
+pub fn activity_heatmap(
+    app: tauri::AppHandle,
+    from_ms: u64,
+    to_ms: u64,
+) -> Result<Vec<DayCount>, String> {
+    if to_ms < from_ms {
+        return Err("Range end must not be before its start".to_string());
+    }
+    let first = dates::day_of(from_ms);
+    let last = dates::day_of(to_ms);
+    if last - first >= MAX_HEATMAP_DAYS {
+        return Err(format!("Range must span at most {MAX_HEATMAP_DAYS} days"));
+    }
+
+    // Only the notes dir itself is scanned, so trash and archive never count.
+    let dir = ensure_notes_dir(&app)?;
+    let mut counts: HashMap<i64, usize> = HashMap::new();
+    for (_, path) in library_notes(&app, &dir)? {
+        let (_, modified) = file_times_ms(&path)?;
+        if (from_ms..=to_ms).contains(&modified) {
+            *counts.entry(dates::day_of(modified)).or_default() += 1;
+        }
+    }
+
+    Ok((first..=last)
+        .map(|day| DayCount {
+            date: dates::format_day(day),
+            count: counts.get(&day).copied().unwrap_or(0),
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn note_extremes(app: tauri::AppHandle) -> Result<Extremes, String> {
+    let dir = ensure_notes_dir(&app)?;
+
+    let mut longest: Option<(String, usize)> = None;
+    let mut shortest: Option<(String, usize)> = None;
+    let mut total = 0usize;
+    let mut counted = 0usize;
+    for (id, path) in library_notes(&app, &dir)? {
+        let words = word_count(&read_note_content(&path)?);
+        if words == 0 {
+            continue;
+        }
+        total += words;
+        counted += 1;
+
+        // Ties go to the smaller id so the answer doesn't depend on dir order.
+        let beats = |best: &Option<(String, usize)>, better: fn(usize, usize) -> bool| match best {
+            None => true,
+            Some((best_id, best_words)) => {
+                better(words, *best_words) || (words == *best_words && id < *best_id)
+            }
+        };
+        if beats(&longest, |a, b| a > b) {
+            longest = Some((id.clone(), words));
+        }
+        if beats(&shortest, |a, b| a < b) {
+            shortest = Some((id, words));
+        }
+    }
+
+    let to_words = |(id, words): (String, usize)| NoteWords { id, words };
+    Ok(Extremes {
+        longest: longest.map(to_words),
+        shortest: shortest.map(to_words),
+        average_words: (counted > 0).then(|| total as f64 / counted as f64),
+    })
+}
+
+/// Notes with at least `min_words` words and their counts, longest first.
+#[tauri::command]
+pub fn long_notes(app: tauri::AppHandle, min_words: usize) -> Result<Vec<(String, usize)>, String> {
+    let dir = ensure_notes_dir(&app)?;
+
+    let mut notes = Vec::new();
+    for (id, path) in library_notes(&app, &dir)? {
+        let words = word_count(&read_note_content(&path)?);
+        if words >= min_words {
+            notes.push((id, words));
+        }
+    }
+    notes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(notes)
+}
+
+/// UTC days on which the note at `path` was edited: its modification time
+/// plus the times its stored versions were replaced.
+fn edit_days(dir: &Path, id: &str, path: &Path) -> Result<BTreeSet<i64>, String> {
+    let (_, modified) = file_times_ms(path)?;
+    let mut days: BTreeSet<i64> = history::versions(dir, id)?
+        .into_iter()
+        .map(|(ms, _)| dates::day_of(ms))
+        .collect();
+    days.insert(dates::day_of(modified));
+    Ok(days)
+}
+
+#[tauri::command]
+pub fn writing_streak(app: tauri::AppHandle) -> Result<StreakInfo, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let mut days = BTreeSet::new();
+    for (id, path) in library_notes(&app, &dir)? {
+        days.extend(edit_days(&dir, &id, &path)?);
+    }
+
+    let mut longest = 0usize;
+    let mut run = 0usize;
+    let mut prev: Option<i64> = None;
+    for &day in &days {
+        run = if prev == Some(day - 1) { run + 1 } else { 1 };
+        longest = longest.max(run);
+        prev = Some(day);
+    }
+
+    // A streak still counts before today's first edit.
+    let today = dates::day_of(system_time_ms(std::time::SystemTime::now()));
+    let current = match prev {
+        Some(last) if last >= today - 1 => run,
+        _ => 0,
+    };
+
+    Ok(StreakInfo {
+        current,
+        longest,
+        last_day: prev.map(dates::format_day),
+    })
+}
+
+/// Approximate number of distinct days the note was edited on, from its
+/// version history and modification time.
+#[tauri::command]
+pub fn note_edit_count(app: tauri::AppHandle, id: String) -> Result<usize, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    Ok(edit_days(&dir, &sanitize_id(&id), &path)?.len())
+}
+
+/// UTC day a note was created: the time in a `note_<ms>` id, else a
+/// `created: YYYY-MM-DD` field (as imports write), else the file's birth time.
+fn creation_day(id: &str, path: &Path) -> Result<i64, String> {
+    if let Some(ms) = id
+        .strip_prefix("note_")
+        .and_then(|ms| ms.parse::<u64>().ok())
+    {
+        return Ok(dates::day_of(ms));
+    }
+    let created = frontmatter::field(&read_note_content(path)?, "created");
+    if let Some(day) = created.as_deref().and_then(dates::parse_day) {
+        return Ok(day);
+    }
+    Ok(dates::day_of(file_times_ms(path)?.0))
+}
+
+/// First day of the bucket holding `day`.
+fn bucket_start(bucket: Bucket, day: i64) -> i64 {
+    match bucket {
+        Bucket::Day => day,
+        // 1970-01-01 was a Thursday, three days after a Monday.
+        Bucket::Week => day - (day + 3).rem_euclid(7),
+        Bucket::Month => {
+            let (year, month, _) = dates::civil_from_days(day);
+            dates::days_from_civil(year, month, 1)
+        }
+    }
+}
+
+fn next_bucket(bucket: Bucket, start: i64) -> i64 {
+    match bucket {
+        Bucket::Day => start + 1,
+        Bucket::Week => start + 7,
+        Bucket::Month => {
+            let (year, month, _) = dates::civil_from_days(start);
+            match month {
+                12 => dates::days_from_civil(year + 1, 1, 1),
+                _ => dates::days_from_civil(year, month + 1, 1),
+            }
+        }
+    }
+}
+
+/// Notes created per day, week or month, from the first bucket with a note
+/// to the last; buckets in between without any are included with 0.
+#[tauri::command]
+pub fn creation_rate(app: tauri::AppHandle, bucket: Bucket) -> Result<Vec<BucketCount>, String> {
+    let dir = ensure_notes_dir(&app)?;
+
+    let mut counts: HashMap<i64, usize> = HashMap::new();
+    for (id, path) in library_notes(&app, &dir)? {
+        let start = bucket_start(bucket, creation_day(&id, &path)?);
+        *counts.entry(start).or_default() += 1;
+    }
+    let (Some(&first), Some(&last)) = (counts.keys().min(), counts.keys().max()) else {
+        return Ok(Vec::new());
+    };
+
+    let mut buckets = Vec::new();
+    let mut start = first;
+    while start <= last {
+        buckets.push(BucketCount {
+            start: dates::format_day(start),
+            count: counts.get(&start).copied().unwrap_or(0),
+        });
+        start = next_bucket(bucket, start);
+    }
+    Ok(buckets)
+}
+
+/// Adds the words of a note's body (outside code fences) to `counts`:
+/// lowercased, split on anything but letters, digits and inner apostrophes,
+/// without stopwords and bare numbers.
+fn count_words(content: &str, counts: &mut HashMap<String, usize>) {
+    for (line, in_fence) in lines_with_fences(frontmatter::body(content)) {
+        if in_fence {
+            continue;
+        }
+        for word in line.split(|c: char| !c.is_alphanumeric() && c != '\'') {
+            let word = word.trim_matches('\'').to_lowercase();
+            if word.is_empty()
+                || word.chars().all(|c| c.is_ascii_digit())
+                || STOPWORDS.contains(&word.as_str())
+            {
+                continue;
+            }
+            *counts.entry(word).or_default() += 1;
+        }
+    }
+}
+
+/// The `top` most frequent words of one note, or of every note when `id` is
+/// unset, most frequent first.
+#[tauri::command]
+pub fn word_frequency(
+    app: tauri::AppHandle,
+    id: Option<String>,
+    top: usize,
+) -> Result<Vec<(String, usize)>, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    let paths = match id {
+        Some(id) => vec![existing_note_path(&dir, &id)?],
+        None => library_notes(&app, &dir)?
+            .into_iter()
+            .map(|(_, path)| path)
+            .collect(),
+    };
+
+    let mut counts = HashMap::new();
+    for path in paths {
+        count_words(&read_note_content(&path)?, &mut counts);
+    }
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.truncate(top);
+    Ok(ranked)
+}
+
+/// When a trashed note was moved to the trash. Unix updates a file's change
+/// time on rename; elsewhere the modification time is the best guess.
+fn trashed_ms(path: &Path) -> Result<u64, String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let meta = std::fs::metadata(path)
+            .map_err(|e| format!("Failed to read file metadata ({}): {e}", path.display()))?;
+        Ok((meta.ctime().max(0) as u64) * 1000)
+    }
+    #[cfg(not(unix))]
+    {
+        Ok(file_times_ms(path)?.1)
+    }
+}
+
+fn words_of(bytes: &[u8]) -> i64 {
+    word_count(&String::from_utf8_lossy(bytes)) as i64
+}
+
+/// Notes created, modified and deleted (trashed or removed outright) on the
+/// UTC day containing `day_ms`, with word count changes taken from history.
+/// With `notify`, the totals are also shown as a notification.
+#[tauri::command]
+pub fn daily_digest(
+    app: tauri::AppHandle,
+    day_ms: u64,
+    notify: Option<bool>,
+) -> Result<Digest, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    let day = dates::day_of(day_ms);
+    let start = day as u64 * dates::MS_PER_DAY;
+    let end = start + dates::MS_PER_DAY;
+
+    let mut digest = Digest {
+        date: dates::format_day(day),
+        created: Vec::new(),
+        modified: Vec::new(),
+        deleted: Vec::new(),
+    };
+    let mut notes = library_notes(&app, &dir)?;
+    notes.sort();
+    for (id, path) in notes {
+        let (_, modified) = file_times_ms(&path)?;
+        let created_today = creation_day(&id, &path)? == day;
+        if !created_today && !(start..end).contains(&modified) {
+            continue;
+        }
+
+        let at_end = match history::version_before(&dir, &id, end)? {
+            Some((_, content)) => content,
+            None => std::fs::read(&path).map_err(|e| format!("Failed to read note file: {e}"))?,
+        };
+        // The start of the day is only known if a save that day replaced it;
+        // writes that skip history leave the delta unknown.
+        let words_delta = if created_today {
+            Some(words_of(&at_end))
+        } else {
+            history::version_before(&dir, &id, start)?
+                .filter(|(replaced, _)| *replaced < end)
+                .map(|(_, at_start)| words_of(&at_end) - words_of(&at_start))
+        };
+        let note = DigestNote { id, words_delta };
+        if created_today {
+            digest.created.push(note);
+        } else {
+            digest.modified.push(note);
+        }
+    }
+
+    let trash = dir.join(TRASH_DIR);
+    if trash.is_dir() {
+        for (id, path) in note_files(&trash)? {
+            if (start..end).contains(&trashed_ms(&path)?) {
+                digest.deleted.push(id);
+            }
+        }
+    }
+    digest
+        .deleted
+        .extend(trash::deleted_between(&dir, start, end)?);
+    digest.deleted.sort();
+    digest.deleted.dedup();
+
+    if notify.unwrap_or(false) {
+        let body = format!(
+            "{} created, {} modified, {} deleted",
+            digest.created.len(),
+            digest.modified.len(),
+            digest.deleted.len()
+        );
+        let shown = app
+            .notification()
+            .builder()
+            .title(format!("Daily digest: {}", digest.date))
+            .body(body)
+            .show();
+        if let Err(e) = shown {
+            eprintln!("Failed to show notification: {e}");
+        }
+    }
+    Ok(digest)
+}
@@ -0,0 +1,458 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cache, ensure_notes_dir, existing_note_path, file_times_ms, frontmatter, library_notes, links,
+    markdown::{is_fence, lines_with_fences},
+    metadata, note_record, pin, read_note_content, sanitize_id, settings, sidecar, similarity,
+    transaction::Transaction,
+    views, NoteRecord,
+};
+
+// Per-tag positions for board views, kept with the library so they travel
+// with it. Tag -> note id -> position.
+const TAG_ORDER_FILE: &str = "tag_order.json";
+
+type TagOrder = HashMap<String, HashMap<String, i32>>;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagActivity {
+    tag: String,
+    notes: usize,
+    /// Latest modification time among the tag's notes.
+    last_modified_ms: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PromoteResult {
+    id: String,
+    /// Hashtags added to the frontmatter tags; ones already there aren't listed.
+    promoted: Vec<String>,
+    error: Option<String>,
+}
+
+fn tag_order_path(dir: &Path) -> PathBuf {
+    dir.join(TAG_ORDER_FILE)
+}
+
+/// Stages tag positions moved to renamed ids as part of `txn`.
+pub fn stage_renames(
+    dir: &Path,
+    txn: &mut Transaction,
+    renames: &HashMap<String, String>,
+) -> Result<(), String> {
+    let path = tag_order_path(dir);
+    let mut order: TagOrder = sidecar::load(&path)?;
+    let mut moved = false;
+    for positions in order.values_mut() {
+        moved |= sidecar::rekey(positions, renames);
+    }
+    if moved {
+        sidecar::stage(txn, &path, &order)?;
+    }
+    Ok(())
+}
+
+/// Drops positions of notes that no longer exist. Returns `tag/id` for each
+/// entry removed.
+pub fn retain_tag_order(dir: &Path, ids: &HashSet<String>) -> Result<Vec<String>, String> {
+    let path = tag_order_path(dir);
+    let mut order: TagOrder = sidecar::load(&path)?;
+    let mut dropped = Vec::new();
+    for (tag, positions) in order.iter_mut() {
+        positions.retain(|id, _| {
+            let keep = ids.contains(id);
+            if !keep {
+                dropped.push(format!("{tag}/{id}"));
+            }
+            keep
+        });
+    }
+    order.retain(|_, positions| !positions.is_empty());
+
+    if !dropped.is_empty() {
+        dropped.sort();
+        sidecar::save(&path, &order)?;
+    }
+    Ok(dropped)
+}
+
+/// Canonical form of a tag for storage and comparison: trimmed, without a
+/// leading `#`, lowercased.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').trim().to_lowercase()
+}
+
+/// The note's tags from its `tags` frontmatter field, normalized and
+/// deduplicated in order of appearance.
+pub fn note_tags(content: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in frontmatter::list(content, "tags") {
+        let tag = normalize_tag(&tag);
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// `#hashtags` written in the note body, normalized and deduplicated. Only a
+/// `#` at the start of a word counts, so headings, URL fragments and link
+/// anchors (`(#section)`) are skipped, as are code fences, inline code and
+/// purely numeric tags like `#1`.
+pub fn inline_hashtags(content: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for (line, in_fence) in lines_with_fences(frontmatter::body(content)) {
+        if in_fence {
+            continue;
+        }
+        for (_, tag) in hashtag_spans(line) {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+    }
+    tags
+}
+
+/// Byte range (`#` included) and normalized tag of each hashtag on `line`,
+/// by the rules of [`inline_hashtags`].
+fn hashtag_spans(line: &str) -> Vec<(Range<usize>, String)> {
+    let mut spans = Vec::new();
+    let mut in_code = false;
+    let mut prev: Option<char> = None;
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let at_word_start = prev.is_none_or(char::is_whitespace);
+        prev = Some(c);
+        if c == '`' {
+            in_code = !in_code;
+        }
+        if in_code || c != '#' || !at_word_start {
+            continue;
+        }
+
+        let start = i + 1;
+        let mut end = start;
+        while let Some(&(j, next)) = chars.peek() {
+            if !(next.is_alphanumeric() || matches!(next, '-' | '_' | '/')) {
+                break;
+            }
+            end = j + next.len_utf8();
+            prev = Some(next);
+            chars.next();
+        }
+
+        let tag = normalize_tag(&line[start..end]);
+        if !tag.is_empty() && !tag.chars().all(|c| c.is_ascii_digit()) {
+            spans.push((i..end, tag));
+        }
+    }
+    spans
+}
+
+/// `body` with every inline hashtag removed, along with the space before it.
+/// Lines left empty by the removal are dropped.
+fn strip_hashtags(body: &str) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut in_fence = false;
+    for line in body.split_inclusive('\n') {
+        if is_fence(line) {
+            in_fence = !in_fence;
+        }
+        let spans = hashtag_spans(line);
+        if in_fence || is_fence(line) || spans.is_empty() {
+            out.push_str(line);
+            continue;
+        }
+
+        let mut stripped = String::with_capacity(line.len());
+        let mut last = 0;
+        for (range, _) in spans {
+            let kept = &line[last..range.start];
+            stripped.push_str(kept.strip_suffix([' ', '\t']).unwrap_or(kept));
+            last = range.end;
+        }
+        let rest = &line[last..];
+        if stripped.is_empty() {
+            // Don't start the line with the space that followed the tag.
+            stripped.push_str(rest.strip_prefix([' ', '\t']).unwrap_or(rest));
+        } else {
+            stripped.push_str(rest);
+        }
+        if !stripped.trim().is_empty() {
+            out.push_str(&stripped);
+        }
+    }
+    out
+}
+
+/// Frontmatter tags, followed by inline hashtags when `inline` is set.
+pub fn all_tags(content: &str, inline: bool) -> Vec<String> {
+    let mut tags = note_tags(content);
+    if inline {
+        for tag in inline_hashtags(content) {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+    }
+    tags
+}
+
+pub fn has_tag(content: &str, tag: &str) -> bool {
+    note_tags(content).contains(&normalize_tag(tag))
+}
+
+/// Pairs of distinct tags that appear on the same note, with the number of
+/// notes carrying both, most frequent first.
+#[tauri::command]
+pub fn tag_cooccurrence(app: tauri::AppHandle) -> Result<Vec<(String, String, usize)>, String> {
+    let dir = ensure_notes_dir(&app)?;
+
+    let inline = settings::load(&app)?.inline_hashtags;
+    let storage = metadata::active(&app)?;
+
+    let mut counts: HashMap<(String, String), usize> = HashMap::new();
+    for (_, path) in library_notes(&app, &dir)? {
+        let mut tags = all_tags(&metadata::read_view(storage, &path)?, inline);
+        tags.sort();
+        for (i, a) in tags.iter().enumerate() {
+            for b in &tags[i + 1..] {
+                *counts.entry((a.clone(), b.clone())).or_default() += 1;
+            }
+        }
+    }
+
+    let mut pairs: Vec<(String, String, usize)> =
+        counts.into_iter().map(|((a, b), n)| (a, b, n)).collect();
+    pairs.sort_by(|x, y| y.2.cmp(&x.2).then_with(|| (&x.0, &x.1).cmp(&(&y.0, &y.1))));
+    Ok(pairs)
+}
+
+/// Sets the position of a note within one tag's view; lower comes first.
+#[tauri::command]
+pub fn set_tag_order(
+    app: tauri::AppHandle,
+    tag: String,
+    id: String,
+    order: i32,
+) -> Result<(), String> {
+    let dir = ensure_notes_dir(&app)?;
+    existing_note_path(&dir, &id)?;
+    let tag = normalize_tag(&tag);
+    if tag.is_empty() {
+        return Err("Tag must not be empty".to_string());
+    }
+
+    let path = tag_order_path(&dir);
+    let mut orders: TagOrder = sidecar::load(&path)?;
+    orders
+        .entry(tag)
+        .or_default()
+        .insert(sanitize_id(&id), order);
+    sidecar::save(&path, &orders)
+}
+
+/// Notes carrying `tag`: those with a position from `set_tag_order` first, in
+/// that order, then the rest in the same order as `list_notes`.
+#[tauri::command]
+pub fn list_notes_by_tag(app: tauri::AppHandle, tag: String) -> Result<Vec<NoteRecord>, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    let tag = normalize_tag(&tag);
+    if tag.is_empty() {
+        return Err("Tag must not be empty".to_string());
+    }
+
+    let inline = settings::load(&app)?.inline_hashtags;
+    let orders: TagOrder = sidecar::load(&tag_order_path(&dir))?;
+    let positions = orders.get(&tag);
+
+    let views = views::load(&app)?;
+    let storage = metadata::active(&app)?;
+    let mut notes = Vec::new();
+    for (id, path) in library_notes(&app, &dir)? {
+        let record = note_record(id, &path, &views, storage)?;
+        let shown = metadata::view(storage, &path, record.content.clone())?;
+        if all_tags(&shown, inline).contains(&tag) {
+            notes.push(record);
+        }
+    }
+
+    let position = |id: &str| positions.and_then(|p| p.get(id)).copied();
+    notes.sort_by(|a, b| match (position(&a.id), position(&b.id)) {
+        (Some(x), Some(y)) => x.cmp(&y).then_with(|| b.id.cmp(&a.id)),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => b.id.cmp(&a.id),
+    });
+    Ok(notes)
+}
+
+/// Every tag in the library with the number of notes carrying it, most used
+/// first. Inline hashtags count when enabled in settings.
+#[tauri::command]
+pub fn list_all_tags(app: tauri::AppHandle) -> Result<Vec<(String, usize)>, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let inline = settings::load(&app)?.inline_hashtags;
+    let storage = metadata::active(&app)?;
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for (_, path) in library_notes(&app, &dir)? {
+        for tag in all_tags(&metadata::read_view(storage, &path)?, inline) {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+
+    let mut tags: Vec<(String, usize)> = counts.into_iter().collect();
+    tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(tags)
+}
+
+fn promote_note(
+    storage: metadata::MetadataStorage,
+    dir: &Path,
+    id: &str,
+    remove_inline: bool,
+) -> Result<Vec<String>, String> {
+    let path = existing_note_path(dir, id)?;
+    let content = read_note_content(&path)?;
+
+    let mut tags = note_tags(&metadata::view(storage, &path, content.clone())?);
+    let promoted: Vec<String> = inline_hashtags(&content)
+        .into_iter()
+        .filter(|tag| !tags.contains(tag))
+        .collect();
+    tags.extend(promoted.iter().cloned());
+
+    let mut updated = content.clone();
+    if remove_inline {
+        let body = frontmatter::body(&content);
+        let head = &content[..content.len() - body.len()];
+        updated = format!("{head}{}", strip_hashtags(body));
+    }
+    if !promoted.is_empty() {
+        let list = frontmatter::inline_list(&tags);
+        updated = metadata::set_raw(storage, &path, &updated, "tags", Some(&list))?;
+    }
+    if updated != content {
+        fs::write(&path, updated).map_err(|e| format!("Failed to write note file: {e}"))?;
+    }
+    Ok(promoted)
+}
+
+/// Copies each note's inline hashtags into its frontmatter `tags`, and with
+/// `remove_inline` strips them from the body. Hashtags in code and URL
+/// fragments are left alone. One note failing doesn't stop the others.
+#[tauri::command]
+pub fn promote_hashtags(
+    app: tauri::AppHandle,
+    ids: Vec<String>,
+    remove_inline: bool,
+) -> Result<Vec<PromoteResult>, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let storage = metadata::active(&app)?;
+
+    let results = ids
+        .into_iter()
+        .map(|id| match promote_note(storage, &dir, &id, remove_inline) {
+            Ok(promoted) => PromoteResult {
+                id,
+                promoted,
+                error: None,
+            },
+            Err(e) => PromoteResult {
+                id,
+                promoted: Vec::new(),
+                error: Some(e),
+            },
+        })
+        .collect();
+    cache::clear(&app);
+    links::invalidate(&app);
+    Ok(results)
+}
+
+/// Up to `limit` tags the note doesn't have yet, best first. Each tag scores
+/// the keyword similarity of every note carrying it, plus a bonus when the
+/// tag itself is one of the note's keywords.
+#[tauri::command]
+pub fn suggest_tags(
+    app: tauri::AppHandle,
+    id: String,
+    limit: usize,
+) -> Result<Vec<String>, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    let id = sanitize_id(&id);
+    let inline = settings::load(&app)?.inline_hashtags;
+    let storage = metadata::active(&app)?;
+
+    let content = metadata::read_view(storage, &path)?;
+    let own_tags = all_tags(&content, inline);
+    let words = similarity::keywords(&content);
+
+    let mut scores: HashMap<String, f64> = HashMap::new();
+    for (other, other_path) in library_notes(&app, &dir)? {
+        if other == id {
+            continue;
+        }
+        let other_content = metadata::read_view(storage, &other_path)?;
+        let tags = all_tags(&other_content, inline);
+        if tags.iter().all(|tag| own_tags.contains(tag)) {
+            continue;
+        }
+        let score = similarity::jaccard(&words, &similarity::keywords(&other_content));
+        for tag in tags {
+            if !own_tags.contains(&tag) {
+                *scores.entry(tag).or_default() += score;
+            }
+        }
+    }
+    for (tag, score) in scores.iter_mut() {
+        if words.contains(tag) {
+            *score += 1.0;
+        }
+    }
+
+    let mut ranked: Vec<(String, f64)> = scores.into_iter().filter(|(_, s)| *s > 0.0).collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(ranked.into_iter().take(limit).map(|(tag, _)| tag).collect())
+}
+
+/// Every tag with its note count and when one of its notes last changed,
+/// most recently active first.
+#[tauri::command]
+pub fn tag_activity(app: tauri::AppHandle) -> Result<Vec<TagActivity>, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let inline = settings::load(&app)?.inline_hashtags;
+    let storage = metadata::active(&app)?;
+
+    let mut activity: HashMap<String, TagActivity> = HashMap::new();
+    for (_, path) in library_notes(&app, &dir)? {
+        let (_, modified) = file_times_ms(&path)?;
+        for tag in all_tags(&metadata::read_view(storage, &path)?, inline) {
+            let entry = activity.entry(tag.clone()).or_insert(TagActivity {
+                tag,
+                notes: 0,
+                last_modified_ms: 0,
+            });
+            entry.notes += 1;
+            entry.last_modified_ms = entry.last_modified_ms.max(modified);
+        }
+    }
+
+    let mut tags: Vec<TagActivity> = activity.into_values().collect();
+    tags.sort_by(|a, b| {
+        b.last_modified_ms
+            .cmp(&a.last_modified_ms)
+            .then_with(|| a.tag.cmp(&b.tag))
+    });
+    Ok(tags)
+}
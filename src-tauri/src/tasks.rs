@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::{
+    cache, ensure_notes_dir, existing_note_path, library_notes,
+    markdown::{is_fence, lines_with_fences},
+    pin, read_note_content, sanitize_id,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskProgress {
+    id: String,
+    done: usize,
+    total: usize,
+    /// `done / total` as 0-100; 0 for notes without checkboxes.
+    percentage: f64,
+}
+
+/// For a GitHub-style task list item (`- [ ] todo`, `* [x] done`, `1. [ ] ...`),
+/// whether it is checked and the byte offset of the mark between the brackets.
+pub fn checkbox(line: &str) -> Option<(bool, usize)> {
+    let indent = line.len() - line.trim_start().len();
+    let rest = &line[indent..];
+
+    let marker_len = if rest.starts_with(['-', '*', '+']) {
+        1
+    } else {
+        let digits = rest.chars().take_while(char::is_ascii_digit).count();
+        if digits == 0 || !rest[digits..].starts_with(['.', ')']) {
+            return None;
+        }
+        digits + 1
+    };
+
+    let after = &rest[marker_len..];
+    let spaces = after.len() - after.trim_start_matches(' ').len();
+    if spaces == 0 {
+        return None;
+    }
+    let boxed = &after[spaces..];
+    let bytes = boxed.as_bytes();
+    if bytes.len() < 3 || bytes[0] != b'[' || bytes[2] != b']' {
+        return None;
+    }
+    if bytes.len() > 3 && !matches!(bytes[3], b' ' | b'\t') {
+        return None;
+    }
+
+    let checked = match bytes[1] {
+        b' ' => false,
+        b'x' | b'X' => true,
+        _ => return None,
+    };
+    Some((checked, indent + marker_len + spaces + 1))
+}
+
+fn progress(id: String, content: &str) -> TaskProgress {
+    let (mut done, mut total) = (0, 0);
+    for (line, in_fence) in lines_with_fences(content) {
+        if in_fence {
+            continue;
+        }
+        if let Some((checked, _)) = checkbox(line) {
+            total += 1;
+            if checked {
+                done += 1;
+            }
+        }
+    }
+
+    let percentage = if total == 0 {
+        0.0
+    } else {
+        done as f64 * 100.0 / total as f64
+    };
+    TaskProgress {
+        id,
+        done,
+        total,
+        percentage,
+    }
+}
+
+#[tauri::command]
+pub fn task_progress(app: tauri::AppHandle, id: String) -> Result<TaskProgress, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    Ok(progress(sanitize_id(&id), &read_note_content(&path)?))
+}
+
+/// Progress of every note that has at least one checkbox.
+#[tauri::command]
+pub fn task_progress_all(app: tauri::AppHandle) -> Result<Vec<TaskProgress>, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+
+    let mut all = Vec::new();
+    for (id, path) in library_notes(&app, &dir)? {
+        let note = progress(id, &read_note_content(&path)?);
+        if note.total > 0 {
+            all.push(note);
+        }
+    }
+    all.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(all)
+}
+
+/// Flips the checkbox on the 1-based `line` of the note and returns its new
+/// state. Only that one character of the file changes.
+#[tauri::command]
+pub fn toggle_checkbox(app: tauri::AppHandle, id: String, line: usize) -> Result<bool, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let path = existing_note_path(&dir, &id)?;
+    let content = read_note_content(&path)?;
+
+    let mut offset = 0;
+    let mut in_fence = false;
+    for (number, text) in content.split_inclusive('\n').enumerate() {
+        if is_fence(text) {
+            in_fence = !in_fence;
+        } else if number + 1 == line && !in_fence {
+            let Some((checked, mark)) = checkbox(text.trim_end_matches(['\n', '\r'])) else {
+                break;
+            };
+
+            let mark = offset + mark;
+            let mut updated = content.clone();
+            updated.replace_range(mark..mark + 1, if checked { " " } else { "x" });
+            fs::write(&path, updated).map_err(|e| format!("Failed to write note file: {e}"))?;
+            cache::invalidate(&app, &sanitize_id(&id));
+            return Ok(!checked);
+        }
+        if number + 1 == line {
+            break;
+        }
+        offset += text.len();
+    }
+    Err(format!("Line {line} does not contain a checkbox"))
+}
@@ -0,0 +1,206 @@
+// All-or-nothing changes to several note files. Every write is staged to a
+// temp file first; only when all of them are on disk do the renames that put
+// them in place run, and a failure there puts back what was already changed.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+enum Op {
+    Write { path: PathBuf, staged: PathBuf },
+    Rename { from: PathBuf, to: PathBuf },
+}
+
+/// What to do to reverse one applied step.
+enum Undo {
+    /// Move the original file back from its backup.
+    Restore {
+        backup: PathBuf,
+        path: PathBuf,
+    },
+    /// Remove a file that didn't exist before.
+    Remove(PathBuf),
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+    },
+}
+
+#[derive(Default)]
+pub struct Transaction {
+    ops: Vec<Op>,
+    content: Vec<Vec<u8>>,
+}
+
+// Neither suffix ends in `.md`, so leftovers never show up as notes.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{name}.{suffix}"))
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces (or creates) `path` with `content` on commit.
+    pub fn write(&mut self, path: &Path, content: impl Into<Vec<u8>>) {
+        self.ops.push(Op::Write {
+            path: path.to_path_buf(),
+            staged: sibling(path, "txn-tmp"),
+        });
+        self.content.push(content.into());
+    }
+
+    /// Renames `from` to `to` on commit; fails if `to` already exists.
+    pub fn rename(&mut self, from: &Path, to: &Path) {
+        self.ops.push(Op::Rename {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        });
+    }
+
+    fn staged_files(&self) -> impl Iterator<Item = &PathBuf> {
+        self.ops.iter().filter_map(|op| match op {
+            Op::Write { staged, .. } => Some(staged),
+            Op::Rename { .. } => None,
+        })
+    }
+
+    fn stage(&mut self) -> Result<(), String> {
+        let content = std::mem::take(&mut self.content);
+        for (staged, bytes) in self.staged_files().zip(content) {
+            fs::write(staged, bytes)
+                .map_err(|e| format!("Failed to stage {}: {e}", staged.display()))?;
+        }
+        Ok(())
+    }
+
+    fn apply(op: &Op, undo: &mut Vec<Undo>) -> Result<(), String> {
+        match op {
+            Op::Write { path, staged } => {
+                if path.exists() {
+                    let backup = sibling(path, "txn-bak");
+                    fs::rename(path, &backup)
+                        .map_err(|e| format!("Failed to replace {}: {e}", path.display()))?;
+                    undo.push(Undo::Restore {
+                        backup,
+                        path: path.clone(),
+                    });
+                } else {
+                    undo.push(Undo::Remove(path.clone()));
+                }
+                fs::rename(staged, path)
+                    .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+            }
+            Op::Rename { from, to } => {
+                if to.exists() {
+                    return Err(format!("Already exists: {}", to.display()));
+                }
+                fs::rename(from, to).map_err(|e| {
+                    format!(
+                        "Failed to rename {} to {}: {e}",
+                        from.display(),
+                        to.display()
+                    )
+                })?;
+                undo.push(Undo::Rename {
+                    from: to.clone(),
+                    to: from.clone(),
+                });
+                Ok(())
+            }
+        }
+    }
+
+    fn roll_back(undo: Vec<Undo>) {
+        for step in undo.into_iter().rev() {
+            let result = match &step {
+                Undo::Restore { backup, path } => fs::rename(backup, path),
+                Undo::Remove(path) => match fs::remove_file(path) {
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                    other => other,
+                },
+                Undo::Rename { from, to } => fs::rename(from, to),
+            };
+            if let Err(e) = result {
+                eprintln!("Transaction rollback step failed: {e}");
+            }
+        }
+    }
+
+    /// Applies every step in order. On error the files touched so far are put
+    /// back as they were and the error is returned.
+    pub fn commit(mut self) -> Result<(), String> {
+        let staged = self.stage();
+        let mut undo = Vec::new();
+        let result = staged.and_then(|()| {
+            self.ops
+                .iter()
+                .try_for_each(|op| Self::apply(op, &mut undo))
+        });
+
+        match &result {
+            Ok(()) => {
+                for step in undo {
+                    if let Undo::Restore { backup, .. } = step {
+                        let _ = fs::remove_file(backup);
+                    }
+                }
+            }
+            Err(_) => Self::roll_back(undo),
+        }
+        for staged in self.staged_files() {
+            let _ = fs::remove_file(staged);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_library(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("txn-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn snapshot(dir: &Path) -> Vec<(String, String)> {
+        let mut files: Vec<(String, String)> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| {
+                let path = entry.unwrap().path();
+                (
+                    path.file_name().unwrap().to_string_lossy().to_string(),
+                    fs::read_to_string(&path).unwrap(),
+                )
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn failure_mid_transaction_leaves_library_unchanged() {
+        let dir = temp_library("rollback");
+        fs::write(dir.join("a.md"), "links to [[b]]").unwrap();
+        fs::write(dir.join("b.md"), "note b").unwrap();
+        fs::write(dir.join("c.md"), "note c").unwrap();
+        let before = snapshot(&dir);
+
+        // Like renaming b to c: the link rewrite and the new file go through,
+        // then the rename hits an existing note and everything is undone.
+        let mut txn = Transaction::new();
+        txn.write(&dir.join("a.md"), "links to [[c]]");
+        txn.write(&dir.join("new.md"), "created");
+        txn.rename(&dir.join("b.md"), &dir.join("c.md"));
+        assert!(txn.commit().is_err());
+
+        assert_eq!(snapshot(&dir), before);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,255 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    attachments, cache, confirm, ensure_notes_dir, file_times_ms, frontmatter, fsutil, git,
+    history, library_notes, links, maintenance, metadata, move_to_folder, note_files, note_path,
+    read_note_content, sanitize_id, system_time_ms, ATTACHMENTS_DIR, TRASH_DIR,
+};
+
+// One `<ms>\t<id>` line per note deleted outright, which leaves no file
+// behind for the digest to find.
+const DELETE_LOG_FILE: &str = ".deleted.log";
+
+/// Records that note `id` was just deleted permanently. Failures are only
+/// logged; they never stop the delete.
+pub fn log_deleted(dir: &Path, id: &str) {
+    let now = system_time_ms(SystemTime::now());
+    let written = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(DELETE_LOG_FILE))
+        .and_then(|mut file| writeln!(file, "{now}\t{id}"));
+    if let Err(e) = written {
+        eprintln!("Failed to write delete log: {e}");
+    }
+}
+
+/// Ids deleted permanently with a time in `start..end`.
+pub fn deleted_between(dir: &Path, start: u64, end: u64) -> Result<Vec<String>, String> {
+    let path = dir.join(DELETE_LOG_FILE);
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let log = fs::read_to_string(&path).map_err(|e| format!("Failed to read delete log: {e}"))?;
+    Ok(log
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .filter(|(ms, _)| ms.parse::<u64>().is_ok_and(|ms| (start..end).contains(&ms)))
+        .map(|(_, id)| id.to_string())
+        .collect())
+}
+
+/// Soft-deletes a note by moving it into the trash folder.
+pub fn trash_note(dir: &Path, id: &str) -> Result<PathBuf, String> {
+    move_to_folder(dir, TRASH_DIR, id)
+}
+
+/// Whether the note is marked `pinned: true` or `favorite: true`, which keeps
+/// it out of automatic cleanups.
+fn is_kept(content: &str) -> bool {
+    ["pinned", "favorite"]
+        .iter()
+        .any(|key| frontmatter::field(content, key).is_some_and(|v| v == "true"))
+}
+
+/// Trashes notes last modified before `older_than_ms`, skipping pinned and
+/// favorite notes. With `dry_run` nothing moves. Returns the affected ids.
+#[tauri::command]
+pub fn trash_stale(
+    app: tauri::AppHandle,
+    older_than_ms: u64,
+    dry_run: bool,
+) -> Result<Vec<String>, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let storage = metadata::active(&app)?;
+
+    let mut stale = Vec::new();
+    for (id, path) in library_notes(&app, &dir)? {
+        let (_, modified) = file_times_ms(&path)?;
+        if modified >= older_than_ms || is_kept(&metadata::read_view(storage, &path)?) {
+            continue;
+        }
+        stale.push(id);
+    }
+    stale.sort();
+
+    if !dry_run {
+        for id in &stale {
+            trash_note(&dir, id)?;
+        }
+        if !stale.is_empty() {
+            cache::clear(&app);
+            links::invalidate(&app);
+        }
+    }
+    Ok(stale)
+}
+
+/// Permanently deletes everything in the trash. Returns the number of notes
+/// removed.
+#[tauri::command]
+pub fn empty_trash(app: tauri::AppHandle, confirmed: bool) -> Result<usize, String> {
+    confirm::require_confirmation(&app, "empty_trash", confirmed)?;
+    let trash = ensure_notes_dir(&app)?.join(TRASH_DIR);
+    if !trash.is_dir() {
+        return Ok(0);
+    }
+
+    let notes = note_files(&trash)?.len();
+    fs::remove_dir_all(&trash).map_err(|e| format!("Failed to empty trash: {e}"))?;
+    Ok(notes)
+}
+
+/// Whether trash file `trashed` is a copy of note `id`: the same id, or the
+/// `<id>_<n>` it gets when the trash already held one. A `<id>_<n>` that is
+/// itself a note in the library is taken to be that note's copy instead.
+fn is_trashed_copy(id: &str, trashed: &str, library: &[(String, PathBuf)]) -> bool {
+    if trashed == id {
+        return true;
+    }
+    let numbered = trashed
+        .strip_prefix(id)
+        .and_then(|rest| rest.strip_prefix('_'))
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+    numbered && !library.iter().any(|(other, _)| other == trashed)
+}
+
+/// Attachments `content` embeds, by their [`links::attachment_key`]. Refs
+/// that climb out with `..` have no key and are left out.
+fn attachment_keys(dir: &Path, content: &str) -> Vec<String> {
+    links::attachment_refs(content)
+        .iter()
+        .filter_map(|reference| links::attachment_key(dir, reference))
+        .collect()
+}
+
+/// Shreds the attachments with the given keys. Each must resolve (symlinks
+/// included) to a file inside the attachments dir; anything else is skipped.
+fn shred_attachments(dir: &Path, keys: &HashSet<String>) -> Result<(), String> {
+    let Ok(root) = dir.join(ATTACHMENTS_DIR).canonicalize() else {
+        return Ok(());
+    };
+    for key in keys {
+        let Ok(path) = dir.join(key).canonicalize() else {
+            continue;
+        };
+        if path.starts_with(&root) && path.is_file() {
+            fsutil::shred_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Removes every trace of a note: the file, its trash copy, its history,
+/// attachments no other note uses, and its sidecar entries. Files are zeroed
+/// before being unlinked. Backups, checkpoints and git history are left as
+/// they are, and the delete log keeps the id.
+#[tauri::command]
+pub fn purge_note(app: tauri::AppHandle, id: String, confirmed: bool) -> Result<(), String> {
+    confirm::require_confirmation(&app, "purge_note", confirmed)?;
+    let dir = ensure_notes_dir(&app)?;
+    let id = sanitize_id(&id);
+    let trash = dir.join(TRASH_DIR);
+
+    let library = library_notes(&app, &dir)?;
+    let note = library
+        .iter()
+        .find(|(other, _)| *other == id)
+        .map(|(_, path)| path.clone());
+    let mut copies: Vec<PathBuf> = note.iter().cloned().collect();
+    if trash.is_dir() {
+        copies.extend(
+            note_files(&trash)?
+                .into_iter()
+                .filter(|(other, _)| is_trashed_copy(&id, other, &library))
+                .map(|(_, path)| path),
+        );
+    }
+    if copies.is_empty() {
+        return Err(format!("Note not found: {id}"));
+    }
+
+    let mut attachments = HashSet::new();
+    for path in &copies {
+        attachments.extend(attachment_keys(&dir, &read_note_content(path)?));
+    }
+    // Attachments are shared by path, so keep any that another note (trashed
+    // and archived ones included, as they can come back) still embeds.
+    for (_, path) in attachments::every_note(&app, &dir)? {
+        if !copies.contains(&path) {
+            for key in attachment_keys(&dir, &read_note_content(&path)?) {
+                attachments.remove(&key);
+            }
+        }
+    }
+
+    for path in &copies {
+        fsutil::shred_file(path)?;
+        let meta = metadata::sidecar_path(path);
+        if meta.is_file() {
+            fsutil::shred_file(&meta)?;
+        }
+    }
+    shred_attachments(&dir, &attachments)?;
+    let versions = history::history_dir(&dir, &id);
+    if versions.is_dir() {
+        fsutil::shred_dir(&versions)?;
+    }
+
+    let remaining = library_notes(&app, &dir)?
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect();
+    maintenance::drop_stale(&app, &dir, &remaining)?;
+    log_deleted(&dir, &id);
+    cache::clear(&app);
+    links::invalidate(&app);
+    let committed = note.unwrap_or_else(|| note_path(&dir, &id));
+    git::autocommit(&app, &committed, &format!("Delete {id}"));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_library(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("trash-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("notes").join(ATTACHMENTS_DIR)).unwrap();
+        dir
+    }
+
+    #[test]
+    fn purge_never_shreds_outside_attachments() {
+        let base = temp_library("traversal");
+        let dir = base.join("notes");
+        let outside = base.join("outside.txt");
+        fs::write(&outside, "not an attachment").unwrap();
+        let inside = dir.join(ATTACHMENTS_DIR).join("pic.png");
+        fs::write(&inside, "png").unwrap();
+
+        let content = format!(
+            "![](.attachments/../../outside.txt)\n![](./.attachments/pic.png)\n![]({}/.attachments/pic.png)\n",
+            dir.display()
+        );
+        let keys: HashSet<String> = attachment_keys(&dir, &content).into_iter().collect();
+        assert_eq!(keys, HashSet::from([".attachments/pic.png".to_string()]));
+
+        // Even a key that got through is checked against the attachments dir.
+        let escaping = HashSet::from([
+            ".attachments/../../outside.txt".to_string(),
+            ".attachments/pic.png".to_string(),
+        ]);
+        shred_attachments(&dir, &escaping).unwrap();
+        assert!(outside.is_file());
+        assert!(!inside.exists());
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
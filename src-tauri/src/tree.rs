@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    ensure_notes_dir,
+    metadata::{self, MetadataStorage},
+    note_files, note_title, pin,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TreeNote {
+    id: String,
+    title: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FolderNode {
+    /// Folder name; empty for the notes dir itself.
+    name: String,
+    /// Path relative to the notes dir, `/`-separated.
+    path: String,
+    folders: Vec<FolderNode>,
+    notes: Vec<TreeNote>,
+}
+
+fn folder_node(
+    storage: MetadataStorage,
+    dir: &Path,
+    name: String,
+    path: String,
+) -> Result<FolderNode, String> {
+    let mut notes = Vec::new();
+    for (id, file) in note_files(dir)? {
+        let title = note_title(&id, &metadata::read_view(storage, &file)?);
+        notes.push(TreeNote { id, title });
+    }
+    notes.sort_by(|a, b| a.title.to_lowercase().cmp(&b.title.to_lowercase()));
+
+    let mut folders = Vec::new();
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read notes dir: {e}"))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read notes dir entry: {e}"))?;
+        let child = entry.file_name().to_string_lossy().to_string();
+        // Internal storage (.trash, .history, ...) isn't part of the tree.
+        if child.starts_with('.') || !entry.path().is_dir() {
+            continue;
+        }
+        let child_path = if path.is_empty() {
+            child.clone()
+        } else {
+            format!("{path}/{child}")
+        };
+        folders.push(folder_node(storage, &entry.path(), child, child_path)?);
+    }
+    folders.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+
+    Ok(FolderNode {
+        name,
+        path,
+        folders,
+        notes,
+    })
+}
+
+/// Folders and notes (ids and titles only) under the notes dir, including
+/// empty folders.
+#[tauri::command]
+pub fn notes_tree(app: tauri::AppHandle) -> Result<FolderNode, String> {
+    pin::ensure_unlocked(&app)?;
+    let dir = ensure_notes_dir(&app)?;
+    folder_node(metadata::active(&app)?, &dir, String::new(), String::new())
+}
+
+/// Every note under `dir` and its (non-internal) subfolders, as `(id, path)`
+/// with the path relative to `dir` and `/`-separated.
+pub fn nested_notes(dir: &Path) -> Result<Vec<(String, String)>, String> {
+    let mut notes = Vec::new();
+    collect_notes(dir, "", &mut notes)?;
+    Ok(notes)
+}
+
+/// The file of note `id` in one of the folders under `dir`, if any.
+pub fn find_note(dir: &Path, id: &str) -> Option<PathBuf> {
+    let (_, rel) = nested_notes(dir)
+        .ok()?
+        .into_iter()
+        .filter(|(other, rel)| other == id && rel.contains('/'))
+        .min_by(|a, b| a.1.cmp(&b.1))?;
+    Some(dir.join(rel))
+}
+
+fn collect_notes(dir: &Path, prefix: &str, out: &mut Vec<(String, String)>) -> Result<(), String> {
+    for (id, file) in note_files(dir)? {
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        out.push((id, format!("{prefix}{name}")));
+    }
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read notes dir: {e}"))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read notes dir entry: {e}"))?;
+        let child = entry.file_name().to_string_lossy().to_string();
+        if child.starts_with('.') || !entry.path().is_dir() {
+            continue;
+        }
+        collect_notes(&entry.path(), &format!("{prefix}{child}/"), out)?;
+    }
+    Ok(())
+}
+
+/// Ids held by more than one file across the notes dir and its folders,
+/// each with the relative paths sharing it. Wiki links and commands can't
+/// tell those files apart.
+#[tauri::command]
+pub fn duplicate_ids(app: tauri::AppHandle) -> Result<Vec<(String, Vec<String>)>, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let mut by_id: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (id, path) in nested_notes(&dir)? {
+        by_id.entry(id).or_default().push(path);
+    }
+    Ok(by_id
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .map(|(id, mut paths)| {
+            paths.sort();
+            (id, paths)
+        })
+        .collect())
+}
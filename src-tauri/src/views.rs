@@ -0,0 +1,156 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    time::SystemTime,
+};
+use tauri::Manager;
+
+use crate::{
+    ensure_notes_dir, existing_note_path, file_times_ms, library_notes, sanitize_id, sidecar,
+    system_time_ms, transaction::Transaction,
+};
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+/// "Recently read": opened within the last two weeks.
+const RECENT_VIEW_MS: u64 = 14 * DAY_MS;
+/// "Stale": not edited for half a year.
+const STALE_EDIT_MS: u64 = 180 * DAY_MS;
+/// How far back individual view times are kept for `frequently_accessed`.
+const VIEW_LOG_DAYS: u32 = 90;
+
+/// Note id -> when it was last opened. Reader state, so it lives in the app
+/// data dir next to the scroll positions.
+pub type Views = HashMap<String, u64>;
+
+/// Note id -> every time it was opened within the last `VIEW_LOG_DAYS`.
+type ViewLog = HashMap<String, Vec<u64>>;
+
+fn views_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let base = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {e}"))?;
+    Ok(base.join("views.json"))
+}
+
+fn view_log_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(views_path(app)?.with_file_name("view_log.json"))
+}
+
+/// Drops view times older than `VIEW_LOG_DAYS` and notes left without any.
+fn prune_log(log: &mut ViewLog, now: u64) {
+    let cutoff = now.saturating_sub(u64::from(VIEW_LOG_DAYS) * DAY_MS);
+    for times in log.values_mut() {
+        times.retain(|&at| at >= cutoff);
+    }
+    log.retain(|_, times| !times.is_empty());
+}
+
+pub fn load(app: &tauri::AppHandle) -> Result<Views, String> {
+    sidecar::load(&views_path(app)?)
+}
+
+/// Stages view times and view logs moved to renamed ids as part of `txn`.
+pub fn stage_renames(
+    app: &tauri::AppHandle,
+    txn: &mut Transaction,
+    renames: &HashMap<String, String>,
+) -> Result<(), String> {
+    sidecar::stage_rekey::<u64>(txn, &views_path(app)?, renames)?;
+    sidecar::stage_rekey::<Vec<u64>>(txn, &view_log_path(app)?, renames)
+}
+
+/// Drops view times of notes that no longer exist. Returns the ids removed.
+pub fn retain_views(app: &tauri::AppHandle, ids: &HashSet<String>) -> Result<Vec<String>, String> {
+    let path = views_path(app)?;
+    let mut views: Views = sidecar::load(&path)?;
+    let mut dropped: Vec<String> = views
+        .keys()
+        .filter(|id| !ids.contains(*id))
+        .cloned()
+        .collect();
+    if dropped.is_empty() {
+        return Ok(dropped);
+    }
+
+    views.retain(|id, _| ids.contains(id));
+    sidecar::save(&path, &views)?;
+
+    let log_path = view_log_path(app)?;
+    let mut log: ViewLog = sidecar::load(&log_path)?;
+    log.retain(|id, _| ids.contains(id));
+    sidecar::save(&log_path, &log)?;
+
+    dropped.sort();
+    Ok(dropped)
+}
+
+#[tauri::command]
+pub fn record_view(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    let dir = ensure_notes_dir(&app)?;
+    existing_note_path(&dir, &id)?;
+
+    let id = sanitize_id(&id);
+    let now = system_time_ms(SystemTime::now());
+
+    let path = views_path(&app)?;
+    let mut views: Views = sidecar::load(&path)?;
+    views.insert(id.clone(), now);
+    sidecar::save(&path, &views)?;
+
+    let log_path = view_log_path(&app)?;
+    let mut log: ViewLog = sidecar::load(&log_path)?;
+    log.entry(id).or_default().push(now);
+    prune_log(&mut log, now);
+    sidecar::save(&log_path, &log)
+}
+
+/// The `top` most opened notes over the last `days`, with their view
+/// counts, most viewed first. Candidates for pinning. Views further back
+/// than `VIEW_LOG_DAYS` aren't kept, so longer windows count only those.
+#[tauri::command]
+pub fn frequently_accessed(
+    app: tauri::AppHandle,
+    days: u32,
+    top: usize,
+) -> Result<Vec<(String, usize)>, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let log: ViewLog = sidecar::load(&view_log_path(&app)?)?;
+    let since = system_time_ms(SystemTime::now()).saturating_sub(u64::from(days) * DAY_MS);
+
+    let mut counts: Vec<(String, usize)> = library_notes(&app, &dir)?
+        .into_iter()
+        .filter_map(|(id, _)| {
+            let count = log.get(&id)?.iter().filter(|&&at| at >= since).count();
+            (count > 0).then_some((id, count))
+        })
+        .collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts.truncate(top);
+    Ok(counts)
+}
+
+/// Notes opened in the last two weeks that haven't been edited in six
+/// months: reference material that's in use but may be out of date. Most
+/// recently viewed first.
+#[tauri::command]
+pub fn stale_but_read(app: tauri::AppHandle) -> Result<Vec<String>, String> {
+    let dir = ensure_notes_dir(&app)?;
+    let views = load(&app)?;
+    let now = system_time_ms(SystemTime::now());
+
+    let mut notes = Vec::new();
+    for (id, path) in library_notes(&app, &dir)? {
+        let Some(&viewed) = views.get(&id) else {
+            continue;
+        };
+        let (_, modified) = file_times_ms(&path)?;
+        if now.saturating_sub(viewed) <= RECENT_VIEW_MS
+            && now.saturating_sub(modified) >= STALE_EDIT_MS
+        {
+            notes.push((viewed, id));
+        }
+    }
+    notes.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    Ok(notes.into_iter().map(|(_, id)| id).collect())
+}
@@ -0,0 +1,304 @@
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::{
+    collections::{BTreeSet, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+use tauri::{Emitter, Manager};
+
+use crate::{cache, is_md_file, layout::Layout, links, sanitize_id, settings};
+
+/// How long after a pause events for the notes it reported are still taken
+/// to be the OS catching up, rather than new changes.
+const SETTLE: Duration = Duration::from_secs(2);
+
+/// Keeps the watcher alive for the lifetime of the app.
+#[derive(Default)]
+pub struct WatcherState(Mutex<Option<RecommendedWatcher>>);
+
+/// Ids whose content the frontend wants pushed on every change.
+#[derive(Default)]
+pub struct Subscriptions(Mutex<HashSet<String>>);
+
+#[derive(Default)]
+struct PauseState {
+    /// Open pauses; events are held back while any is open.
+    depth: usize,
+    /// Ids changed while paused, reported together on resume.
+    ids: BTreeSet<String>,
+    /// Ids the last `bulk` event reported, and until when late events for
+    /// them are swallowed.
+    settling: BTreeSet<String>,
+    settle_until: Option<Instant>,
+}
+
+impl PauseState {
+    /// Drops the ids (and their paths) the last `bulk` event already
+    /// reported, while its settle window is open.
+    fn unsettled(&mut self, ids: Vec<String>, paths: Vec<PathBuf>) -> (Vec<String>, Vec<PathBuf>) {
+        match self.settle_until {
+            Some(until) if Instant::now() < until => {}
+            Some(_) => {
+                self.settle_until = None;
+                self.settling.clear();
+                return (ids, paths);
+            }
+            None => return (ids, paths),
+        }
+        let ids = ids
+            .into_iter()
+            .filter(|id| !self.settling.contains(id))
+            .collect();
+        let paths = paths
+            .into_iter()
+            .filter(|path| {
+                path.file_stem()
+                    .and_then(|s| s.to_str())
+                    .is_none_or(|id| !self.settling.contains(id))
+            })
+            .collect();
+        (ids, paths)
+    }
+}
+
+/// Lets bulk operations hold back change events until they're done.
+#[derive(Default)]
+pub struct WatcherPause(Mutex<PauseState>);
+
+/// Resumes the watcher when dropped.
+pub struct PauseGuard {
+    app: tauri::AppHandle,
+}
+
+impl Drop for PauseGuard {
+    fn drop(&mut self) {
+        end_pause(&self.app);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct NoteUpdated {
+    id: String,
+    content: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct NotesChanged {
+    /// `created`, `modified`, `removed`, `other`, or `bulk` for everything
+    /// that changed while the watcher was paused.
+    kind: &'static str,
+    ids: Vec<String>,
+}
+
+fn kind_name(kind: &EventKind) -> Option<&'static str> {
+    match kind {
+        EventKind::Create(_) => Some("created"),
+        EventKind::Modify(_) => Some("modified"),
+        EventKind::Remove(_) => Some("removed"),
+        EventKind::Access(_) => None,
+        EventKind::Any | EventKind::Other => Some("other"),
+    }
+}
+
+/// Whether `path` is a note of the library at `dir`. Recursive watching also
+/// reports the internal folders (.trash, .history, ...), which aren't.
+fn is_library_note(dir: &Path, path: &Path) -> bool {
+    if !is_md_file(path) {
+        return false;
+    }
+    // Paths the OS reports in another form than `dir` (symlinks resolved,
+    // say) can't be placed; count them as notes, as before folders were
+    // watched.
+    let Ok(rel) = path.strip_prefix(dir) else {
+        return true;
+    };
+    !rel.components()
+        .any(|part| part.as_os_str().to_string_lossy().starts_with('.'))
+}
+
+fn on_event(app: &tauri::AppHandle, dir: &Path, event: notify::Event) {
+    let Some(kind) = kind_name(&event.kind) else {
+        return;
+    };
+
+    let ids: Vec<String> = event
+        .paths
+        .iter()
+        .filter(|path| is_library_note(dir, path))
+        .filter_map(|path| path.file_stem().and_then(|s| s.to_str()))
+        .map(str::to_string)
+        .collect();
+    if ids.is_empty() {
+        return;
+    }
+
+    links::invalidate(app);
+    for id in &ids {
+        cache::invalidate(app, id);
+    }
+    let (ids, paths) = {
+        let pause = app.state::<WatcherPause>();
+        let mut pause = pause.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if pause.depth > 0 {
+            pause.ids.extend(ids);
+            return;
+        }
+        pause.unsettled(ids, event.paths)
+    };
+    if ids.is_empty() {
+        return;
+    }
+    push_subscribed(app, &paths);
+    if let Err(e) = app.emit("notes-changed", NotesChanged { kind, ids }) {
+        eprintln!("Failed to emit notes-changed: {e}");
+    }
+}
+
+fn push_subscribed(app: &tauri::AppHandle, paths: &[PathBuf]) {
+    let subscribed = app.state::<Subscriptions>();
+    let subscribed = subscribed.0.lock().unwrap_or_else(PoisonError::into_inner);
+    if subscribed.is_empty() {
+        return;
+    }
+
+    for path in paths {
+        let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if !is_md_file(path) || !subscribed.contains(id) {
+            continue;
+        }
+        // Removed (or mid-rename) files have nothing to push; the
+        // notes-changed event covers those.
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+
+        let payload = NoteUpdated {
+            id: id.to_string(),
+            content,
+        };
+        if let Err(e) = app.emit("note-updated", payload) {
+            eprintln!("Failed to emit note-updated: {e}");
+        }
+    }
+}
+
+/// Watches the notes dir and tells the frontend (and the in-memory caches)
+/// about changes made outside of the app's own commands. Replaces any
+/// earlier watcher; call it again when the storage layout changes, as folders
+/// are only watched with the nested layout.
+pub fn start(app: &tauri::AppHandle, dir: &Path) -> Result<(), String> {
+    let mode = match settings::load(app)?.storage_layout {
+        Layout::Flat => RecursiveMode::NonRecursive,
+        Layout::Nested => RecursiveMode::Recursive,
+    };
+
+    let handle = app.clone();
+    let root = dir.to_path_buf();
+    let mut watcher =
+        notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event) => on_event(&handle, &root, event),
+            Err(e) => eprintln!("Notes watcher error: {e}"),
+        })
+        .map_err(|e| format!("Failed to create notes watcher: {e}"))?;
+
+    watcher
+        .watch(dir, mode)
+        .map_err(|e| format!("Failed to watch notes dir: {e}"))?;
+
+    let state = app.state::<WatcherState>();
+    *state.0.lock().unwrap_or_else(PoisonError::into_inner) = Some(watcher);
+    Ok(())
+}
+
+fn begin_pause(app: &tauri::AppHandle) {
+    let pause = app.state::<WatcherPause>();
+    pause.0.lock().unwrap_or_else(PoisonError::into_inner).depth += 1;
+}
+
+/// Closes one pause; the last one emits a single `bulk` notes-changed event.
+/// Events the OS delivers late for the notes it names are swallowed for a
+/// short while after.
+fn end_pause(app: &tauri::AppHandle) {
+    let ids = {
+        let pause = app.state::<WatcherPause>();
+        let mut pause = pause.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if pause.depth == 0 {
+            return;
+        }
+        pause.depth -= 1;
+        if pause.depth > 0 {
+            return;
+        }
+        let ids = std::mem::take(&mut pause.ids);
+        pause.settling = ids.clone();
+        pause.settle_until = Some(Instant::now() + SETTLE);
+        ids
+    };
+
+    links::invalidate(app);
+    let changed = NotesChanged {
+        kind: "bulk",
+        ids: ids.into_iter().collect(),
+    };
+    if let Err(e) = app.emit("notes-changed", changed) {
+        eprintln!("Failed to emit notes-changed: {e}");
+    }
+}
+
+/// Holds back watcher events until the returned guard is dropped, for
+/// commands that touch many files at once. Events for other notes arriving
+/// after the guard is gone are reported individually as usual.
+pub fn pause(app: &tauri::AppHandle) -> PauseGuard {
+    begin_pause(app);
+    PauseGuard { app: app.clone() }
+}
+
+/// Drops subscriptions to notes not in `existing`; returns the dropped ids.
+pub fn retain_subscriptions(app: &tauri::AppHandle, existing: &HashSet<String>) -> Vec<String> {
+    let subscribed = app.state::<Subscriptions>();
+    let mut subscribed = subscribed.0.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut dropped: Vec<String> = subscribed.difference(existing).cloned().collect();
+    subscribed.retain(|id| existing.contains(id));
+    dropped.sort();
+    dropped
+}
+
+#[tauri::command]
+pub fn subscribe_note(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    let subscribed = app.state::<Subscriptions>();
+    subscribed
+        .0
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(sanitize_id(&id));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn unsubscribe_note(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    let subscribed = app.state::<Subscriptions>();
+    subscribed
+        .0
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(&sanitize_id(&id));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn pause_watcher(app: tauri::AppHandle) -> Result<(), String> {
+    begin_pause(&app);
+    Ok(())
+}
+
+/// Ends a `pause_watcher`. Does nothing when the watcher isn't paused.
+#[tauri::command]
+pub fn resume_watcher(app: tauri::AppHandle) -> Result<(), String> {
+    end_pause(&app);
+    Ok(())
+}